@@ -1,5 +1,5 @@
 # flake8: noqa
-from typing import Any, Callable, Generic, List, Optional, Tuple, TypeVar, Union
+from typing import Any, Callable, Dict, Generic, List, Optional, Tuple, TypeVar, Union
 
 # Constants
 VERSION: str
@@ -38,6 +38,26 @@ COLOR_PEACH: int
 
 FONT_WIDTH: int
 FONT_HEIGHT: int
+ALIGN_LEFT: int
+ALIGN_CENTER: int
+ALIGN_RIGHT: int
+STENCIL_OFF: int
+STENCIL_TEST: int
+STENCIL_WRITE: int
+DITHER_NONE: int
+DITHER_ORDERED: int
+DITHER_FLOYD_STEINBERG: int
+QUANTIZE_MEDIAN_CUT: int
+QUANTIZE_K_MEANS: int
+UPSCALE_NONE: int
+UPSCALE_SCALE2X: int
+UPSCALE_SCALE3X: int
+UPSCALE_EPX: int
+AUTOTILE_BLOB16: int
+AUTOTILE_BLOB47: int
+LOOP_ONCE: int
+LOOP_REPEAT: int
+LOOP_PING_PONG: int
 
 NUM_CHANNELS: int
 NUM_TONES: int
@@ -414,8 +434,10 @@ class Seq(Generic[T]):
 
 # Font class
 class Font:
-    def __init__(self, filename: str) -> None: ...
+    def __init__(self, filename: str, font_size: Optional[float] = None) -> None: ...
     def text_width(self, s: str) -> int: ...
+    def set_fallbacks(self, fonts: List[Font]) -> None: ...
+    def set_kerning(self, left: str, right: str, offset: int) -> None: ...
 
 # Image class
 class Image:
@@ -424,13 +446,57 @@ class Image:
 
     def __init__(self, width: int, height: int) -> None: ...
     @staticmethod
-    def from_image(filename: str, *, incl_colors: Optional[bool] = None) -> Image: ...
+    def from_image(
+        filename: str,
+        *,
+        incl_colors: Optional[bool] = None,
+        dither: Optional[int] = None,
+        quantize: Optional[int] = None,
+        num_colors: Optional[int] = None,
+        transparent: Optional[int] = None,
+    ) -> Image: ...
     def data_ptr(self) -> Any: ...
     def set(self, x: int, y: int, data: List[str]) -> None: ...
     def load(
-        self, x: int, y: int, filename: str, *, incl_colors: Optional[bool] = None
+        self,
+        x: int,
+        y: int,
+        filename: str,
+        *,
+        incl_colors: Optional[bool] = None,
+        dither: Optional[int] = None,
+        quantize: Optional[int] = None,
+        num_colors: Optional[int] = None,
+        transparent: Optional[int] = None,
+    ) -> None: ...
+    def load_aseprite(
+        self,
+        x: int,
+        y: int,
+        filename: str,
+        *,
+        layer: Optional[str] = None,
+        columns: Optional[int] = None,
+        incl_colors: Optional[bool] = None,
+    ) -> AsepriteData: ...
+    def save(
+        self,
+        filename: str,
+        scale: int,
+        *,
+        indexed: Optional[bool] = None,
+        transparent: Optional[int] = None,
+        upscale: Optional[int] = None,
     ) -> None: ...
-    def save(self, filename: str, scale: int) -> None: ...
+    def flipped_h(self) -> Image: ...
+    def flipped_v(self) -> Image: ...
+    def rotate90(self) -> Image: ...
+    def crop(self, x: int, y: int, w: int, h: int) -> Image: ...
+    def resize(self, w: int, h: int) -> Image: ...
+    def scale2x(self) -> Image: ...
+    def scale3x(self) -> Image: ...
+    def epx(self) -> Image: ...
+    def upscaled(self, upscale: int) -> Image: ...
     def clip(
         self,
         x: Optional[float] = None,
@@ -445,6 +511,13 @@ class Image:
     ) -> None: ...
     def pal(self, col1: Optional[int] = None, col2: Optional[int] = None) -> None: ...
     def dither(self, alpha: float) -> None: ...
+    def stencil(
+        self, img: Optional[Union[int, Image]] = None, colkey: Optional[int] = None
+    ) -> None: ...
+    def stencil_mode(self, mode: int) -> None: ...
+    def stencil_set(self) -> None: ...
+    def stencil_clear(self) -> None: ...
+    def stencil_invert(self) -> None: ...
     def cls(self, col: int) -> None: ...
     def pget(self, x: float, y: float) -> int: ...
     def pset(self, x: float, y: float, col: int) -> None: ...
@@ -462,6 +535,10 @@ class Image:
         self, x1: float, y1: float, x2: float, y2: float, x3: float, y3: float, col: int
     ) -> None: ...
     def fill(self, x: float, y: float, col: int) -> None: ...
+    def linear_gradient(
+        self, x: float, y: float, w: float, h: float, angle: float, cols: List[int]
+    ) -> None: ...
+    def radial_gradient(self, x: float, y: float, r: float, cols: List[int]) -> None: ...
     def blt(
         self,
         x: float,
@@ -476,11 +553,73 @@ class Image:
         rotate: Optional[float] = None,
         scale: Optional[float] = None,
     ) -> None: ...
+    def blt_stretch(
+        self,
+        x: float,
+        y: float,
+        w: float,
+        h: float,
+        img: Union[int, Image],
+        u: float,
+        v: float,
+        iw: float,
+        ih: float,
+        colkey: Optional[int] = None,
+        *,
+        shear: Optional[float] = None,
+    ) -> None: ...
+    def blt_scale(
+        self,
+        x: float,
+        y: float,
+        img: Union[int, Image],
+        u: float,
+        v: float,
+        w: float,
+        h: float,
+        colkey: Optional[int],
+        scale_x: float,
+        scale_y: float,
+        *,
+        shear: Optional[float] = None,
+    ) -> None: ...
+    def blt_tiled(
+        self,
+        x: float,
+        y: float,
+        w: float,
+        h: float,
+        img: Union[int, Image],
+        u: float,
+        v: float,
+        tw: float,
+        th: float,
+        colkey: Optional[int] = None,
+    ) -> None: ...
+    def blt9(
+        self,
+        x: float,
+        y: float,
+        w: float,
+        h: float,
+        img: Union[int, Image],
+        u: float,
+        v: float,
+        iw: float,
+        ih: float,
+        left: float,
+        top: float,
+        right: float,
+        bottom: float,
+        colkey: Optional[int] = None,
+        *,
+        tiled: Optional[bool] = None,
+    ) -> None: ...
     def bltm(
         self,
         x: float,
         y: float,
-        tm: Union[int, Tilemap],
+        tm: Union[int, Tilemap, ChunkedTilemap],
         u: float,
         v: float,
         w: float,
@@ -489,10 +628,43 @@ class Image:
         *,
         rotate: Optional[float] = None,
         scale: Optional[float] = None,
+        time: Optional[int] = None,
     ) -> None: ...
     def text(
         self, x: float, y: float, s: str, col: int, font: Optional[Font] = None
     ) -> None: ...
+    def text_rich(
+        self,
+        x: float,
+        y: float,
+        s: str,
+        col: int,
+        font: Optional[Font] = None,
+        *,
+        time: Optional[int] = None,
+    ) -> None: ...
+    def text_box(
+        self,
+        x: float,
+        y: float,
+        w: float,
+        h: float,
+        s: str,
+        col: int,
+        font: Optional[Font] = None,
+        *,
+        align: Optional[int] = None,
+        line_spacing: Optional[int] = None,
+        wrap: Optional[bool] = None,
+        ellipsis: Optional[bool] = None,
+    ) -> None: ...
+
+# AsepriteData class
+class AsepriteData:
+    width: int
+    height: int
+    frames: List[Tuple[int, int, int]]
+    tags: List[Tuple[str, int, int]]
 
 # Tilemap class
 class Tilemap:
@@ -506,6 +678,8 @@ class Tilemap:
     def data_ptr(self) -> Any: ...
     def set(self, x: int, y: int, data: List[str]) -> None: ...
     def load(self, x: int, y: int, filename: str, layer: int) -> None: ...
+    def save_tmx(self, filename: str, tileset_filename: Optional[str] = None) -> None: ...
+    def save_csv(self, filename: str) -> None: ...
     def clip(
         self,
         x: Optional[float] = None,
@@ -573,11 +747,217 @@ class Tilemap:
         rotate: Optional[float] = None,
         scale: Optional[float] = None,
     ) -> None: ...
+    def set_animation(
+        self, tile: Tuple[int, int], frames: List[Tuple[Tuple[int, int], int]]
+    ) -> None: ...
+    def set_attr(self, tile: Tuple[int, int], flags: int) -> None: ...
+    def attr(self, tile: Tuple[int, int]) -> int: ...
+    def attr_at(self, x: int, y: int) -> int: ...
+    def rect_tiles(
+        self, x: float, y: float, w: float, h: float, mask: int
+    ) -> List[Tuple[int, int]]: ...
+    def collide_rect(self, x: float, y: float, w: float, h: float, mask: int) -> bool: ...
+    def sweep_rect(
+        self, x: float, y: float, w: float, h: float, dx: float, dy: float, mask: int
+    ) -> Tuple[float, float, bool, bool]: ...
+    def raycast(
+        self, x1: float, y1: float, x2: float, y2: float, mask: int
+    ) -> Optional[Tuple[float, float, int, int]]: ...
+    def autotile(self, x: int, y: int, w: int, h: int, terrain: Terrain) -> None: ...
+    def pset_terrain(self, x: int, y: int, terrain: Terrain) -> None: ...
+    def find_path(
+        self,
+        start: Tuple[int, int],
+        goal: Tuple[int, int],
+        mask: int,
+        *,
+        diagonal: Optional[bool] = None,
+        cut_corners: Optional[bool] = None,
+    ) -> Optional[List[Tuple[int, int]]]: ...
+    def distance_field(
+        self,
+        goals: List[Tuple[int, int]],
+        mask: int,
+        *,
+        diagonal: Optional[bool] = None,
+        cut_corners: Optional[bool] = None,
+    ) -> List[List[float]]: ...
+    def flow_field(
+        self,
+        goals: List[Tuple[int, int]],
+        mask: int,
+        *,
+        diagonal: Optional[bool] = None,
+        cut_corners: Optional[bool] = None,
+    ) -> List[List[Tuple[int, int]]]: ...
 
     # Deprecated field
     image: Image
     refimg: Optional[int]
 
+# Terrain class
+class Terrain:
+    layout: int
+
+    def __init__(self, layout: int) -> None: ...
+    @staticmethod
+    def from_sheet(
+        layout: int, tile_x: int, tile_y: int, columns: Optional[int] = None
+    ) -> Terrain: ...
+    def masks(self) -> List[int]: ...
+    def set_tile(self, mask: int, tile: Tuple[int, int]) -> None: ...
+    def tile(self, mask: int) -> Optional[Tuple[int, int]]: ...
+
+# ChunkedTilemap class
+class ChunkedTilemap:
+    width: int
+    height: int
+    imgsrc: Union[int, Image]
+
+    def __init__(self, width: int, height: int, img: Union[int, Image]) -> None: ...
+    def chunk_coords(self) -> List[Tuple[int, int]]: ...
+    def is_chunk_loaded(self, chunk_x: int, chunk_y: int) -> bool: ...
+    def load_chunk(self, chunk_x: int, chunk_y: int, filename: str) -> None: ...
+    def save_chunk(self, chunk_x: int, chunk_y: int, filename: str) -> None: ...
+    def unload_chunk(self, chunk_x: int, chunk_y: int) -> None: ...
+    def region(self, x: int, y: int, w: int, h: int) -> Tilemap: ...
+    def set_animation(
+        self, tile: Tuple[int, int], frames: List[Tuple[Tuple[int, int], int]]
+    ) -> None: ...
+    def clip(
+        self,
+        x: Optional[float] = None,
+        y: Optional[float] = None,
+        w: Optional[float] = None,
+        h: Optional[float] = None,
+    ) -> None: ...
+    def camera(
+        self,
+        x: Optional[float] = None,
+        y: Optional[float] = None,
+    ) -> None: ...
+    def cls(self, tile: Tuple[int, int]) -> None: ...
+    def pget(self, x: float, y: float) -> Tuple[int, int]: ...
+    def pset(self, x: float, y: float, tile: Tuple[int, int]) -> None: ...
+    def line(
+        self, x1: float, y1: float, x2: float, y2: float, tile: Tuple[int, int]
+    ) -> None: ...
+    def rect(
+        self, x: float, y: float, w: float, h: float, tile: Tuple[int, int]
+    ) -> None: ...
+    def rectb(
+        self, x: float, y: float, w: float, h: float, tile: Tuple[int, int]
+    ) -> None: ...
+    def circ(self, x: float, y: float, r: float, tile: Tuple[int, int]) -> None: ...
+    def circb(self, x: float, y: float, r: float, tile: Tuple[int, int]) -> None: ...
+    def elli(
+        self, x: float, y: float, w: float, h: float, tile: Tuple[int, int]
+    ) -> None: ...
+    def ellib(
+        self, x: float, y: float, w: float, h: float, tile: Tuple[int, int]
+    ) -> None: ...
+    def tri(
+        self,
+        x1: float,
+        y1: float,
+        x2: float,
+        y2: float,
+        x3: float,
+        y3: float,
+        tile: Tuple[int, int],
+    ) -> None: ...
+    def trib(
+        self,
+        x1: float,
+        y1: float,
+        x2: float,
+        y2: float,
+        x3: float,
+        y3: float,
+        tile: Tuple[int, int],
+    ) -> None: ...
+    def fill(self, x: float, y: float, tile: Tuple[int, int]) -> None: ...
+    def blt(
+        self,
+        x: float,
+        y: float,
+        tm: Union[int, Tilemap],
+        u: float,
+        v: float,
+        w: float,
+        h: float,
+        tilekey: Optional[Tuple[int, int]] = None,
+    ) -> None: ...
+
+# ViewCamera class
+class ViewCamera:
+    enabled: bool
+    x: float
+    y: float
+    zoom: float
+    smoothing: float
+
+    def follow(self, x: float, y: float) -> None: ...
+    def unfollow(self) -> None: ...
+    def look_at(self, x: float, y: float) -> None: ...
+    def disable(self) -> None: ...
+    def set_deadzone(self, w: float, h: float) -> None: ...
+    def set_bounds(self, x: float, y: float, w: float, h: float) -> None: ...
+    def clamp_to_tilemap(self, tm: Union[int, Tilemap]) -> None: ...
+    def shake(self, intensity: float, decay: Optional[float] = None) -> None: ...
+    def apply_zoom(self) -> None: ...
+
+# Layer class
+class Layer:
+    image: Image
+    visible: bool
+    colkey: Optional[int]
+
+    def camera(
+        self,
+        x: Optional[float] = None,
+        y: Optional[float] = None,
+    ) -> None: ...
+
+# LDtk classes
+class LdtkProject:
+    levels: List[LdtkLevel]
+    tileset_banks: Dict[int, int]
+
+    @staticmethod
+    def load(filename: str, first_bank: Optional[int] = None) -> LdtkProject: ...
+    def level(self, identifier: str) -> Optional[LdtkLevel]: ...
+
+class LdtkLevel:
+    identifier: str
+    world_x: int
+    world_y: int
+    width: int
+    height: int
+    layers: List[LdtkLayer]
+    entities: List[LdtkEntity]
+    fields: Dict[str, Any]
+
+    def layer(self, identifier: str) -> Optional[LdtkLayer]: ...
+    def find_entities(self, identifier: str) -> List[LdtkEntity]: ...
+
+class LdtkLayer:
+    identifier: str
+    tilemap: Tilemap
+    int_grid: List[int]
+
+class LdtkEntity:
+    identifier: str
+    x: int
+    y: int
+    grid_x: int
+    grid_y: int
+    width: int
+    height: int
+    fields: Dict[str, Any]
+
+    def field(self, name: str) -> Any: ...
+
 # Channel class
 class Channel:
     gain: float
@@ -641,6 +1021,53 @@ class Music:
     # Deprecated field
     snds_list: Seq[Seq[int]]
 
+# Sprite class
+class Sprite:
+    animations: List[str]
+    current: Optional[str]
+
+    def __init__(self) -> None: ...
+    def add_animation(
+        self,
+        name: str,
+        frames: List[Tuple[int, float, float, float, float, int]],
+        loop_mode: Optional[int] = None,
+    ) -> None: ...
+    def add_aseprite_animation(
+        self, name: str, data: AsepriteData, img: int, tag: Optional[str] = None
+    ) -> None: ...
+    def play(self, name: str, restart: Optional[bool] = None) -> None: ...
+    def pause(self) -> None: ...
+    def resume(self) -> None: ...
+    def is_playing(self) -> bool: ...
+    def is_finished(self) -> bool: ...
+    def frame(self) -> Optional[Tuple[int, float, float, float, float]]: ...
+
+# Emitter class
+class Emitter:
+    x: float
+    y: float
+    active: bool
+    spawn_rate: float
+    max_particles: int
+    lifetime: Tuple[int, int]
+    speed: Tuple[float, float]
+    angle: Tuple[float, float]
+    gravity: Tuple[float, float]
+    drag: float
+    colors: List[int]
+    colkey: Optional[int]
+    num_particles: int
+
+    def __init__(self, x: float, y: float) -> None: ...
+    def shape_pixel(self) -> None: ...
+    def shape_circle(self, radius: float) -> None: ...
+    def shape_sprite(self, img: int, u: float, v: float, w: float, h: float) -> None: ...
+    def seed(self, seed: int) -> None: ...
+    def burst(self, count: int) -> None: ...
+    def update(self) -> None: ...
+    def clear(self) -> None: ...
+
 # System
 width: int
 height: int
@@ -692,7 +1119,7 @@ def save(
     incl_channels: Optional[bool] = None,
     incl_tones: Optional[bool] = None,
 ) -> None: ...
-def screenshot(scale: Optional[int] = None) -> None: ...
+def screenshot(scale: Optional[int] = None, upscale: Optional[int] = None) -> None: ...
 def screencast(scale: Optional[int] = None) -> None: ...
 def reset_screencast() -> None: ...
 def user_data_dir(vendor_name: str, app_name: str) -> str: ...
@@ -721,6 +1148,7 @@ tilemaps: Seq[Tilemap]
 screen: Image
 cursor: Image
 font: Image
+view_camera: ViewCamera
 
 def clip(
     x: Optional[float] = None,
@@ -733,7 +1161,21 @@ def camera(
     y: Optional[float] = None,
 ) -> None: ...
 def pal(col1: Optional[int] = None, col2: Optional[int] = None) -> None: ...
+def pal_cycle(
+    start: Optional[int] = None,
+    end: Optional[int] = None,
+    interval: Optional[int] = None,
+    *,
+    reverse: Optional[bool] = None,
+) -> None: ...
 def dither(alpha: float) -> None: ...
+def stencil(
+    img: Optional[Union[int, Image]] = None, colkey: Optional[int] = None
+) -> None: ...
+def stencil_mode(mode: int) -> None: ...
+def stencil_set() -> None: ...
+def stencil_clear() -> None: ...
+def stencil_invert() -> None: ...
 def cls(col: int) -> None: ...
 def pget(x: float, y: float) -> int: ...
 def pset(x: float, y: float, col: int) -> None: ...
@@ -763,6 +1205,10 @@ def trib(
     col: int,
 ) -> None: ...
 def fill(x: float, y: float, col: int) -> None: ...
+def linear_gradient(
+    x: float, y: float, w: float, h: float, angle: float, cols: List[int]
+) -> None: ...
+def radial_gradient(x: float, y: float, r: float, cols: List[int]) -> None: ...
 def blt(
     x: float,
     y: float,
@@ -776,10 +1222,84 @@ def blt(
     rotate: Optional[float] = None,
     scale: Optional[float] = None,
 ) -> None: ...
+def blt_stretch(
+    x: float,
+    y: float,
+    w: float,
+    h: float,
+    img: Union[int, Image],
+    u: float,
+    v: float,
+    iw: float,
+    ih: float,
+    colkey: Optional[int] = None,
+    *,
+    shear: Optional[float] = None,
+) -> None: ...
+def blt_scale(
+    x: float,
+    y: float,
+    img: Union[int, Image],
+    u: float,
+    v: float,
+    w: float,
+    h: float,
+    colkey: Optional[int],
+    scale_x: float,
+    scale_y: float,
+    *,
+    shear: Optional[float] = None,
+) -> None: ...
+def blt_tiled(
+    x: float,
+    y: float,
+    w: float,
+    h: float,
+    img: Union[int, Image],
+    u: float,
+    v: float,
+    tw: float,
+    th: float,
+    colkey: Optional[int] = None,
+) -> None: ...
+def blt9(
+    x: float,
+    y: float,
+    w: float,
+    h: float,
+    img: Union[int, Image],
+    u: float,
+    v: float,
+    iw: float,
+    ih: float,
+    left: float,
+    top: float,
+    right: float,
+    bottom: float,
+    colkey: Optional[int] = None,
+    *,
+    tiled: Optional[bool] = None,
+) -> None: ...
+def blt_sprite(
+    x: float,
+    y: float,
+    name: str,
+    colkey: Optional[int] = None,
+    *,
+    rotate: Optional[float] = None,
+    scale: Optional[float] = None,
+) -> None: ...
+def pack_atlas(
+    images: Union[str, List[Tuple[str, Image]]], bank: Optional[int] = None
+) -> None: ...
+def load_atlas(filename: str) -> None: ...
+def save_atlas(filename: str) -> None: ...
+def atlas_entry(name: str) -> Optional[Tuple[int, int, int, int, int]]: ...
+def bltm_camera(tm: int, colkey: Optional[int] = None) -> None: ...
 def bltm(
     x: float,
     y: float,
-    tm: Union[int, Tilemap],
+    tm: Union[int, Tilemap, ChunkedTilemap],
     u: float,
     v: float,
     w: float,
@@ -788,8 +1308,52 @@ def bltm(
     *,
     rotate: Optional[float] = None,
     scale: Optional[float] = None,
+    time: Optional[int] = None,
+) -> None: ...
+def draw_sprite(
+    x: float,
+    y: float,
+    sprite: Sprite,
+    flip_x: Optional[bool] = None,
+    flip_y: Optional[bool] = None,
+    colkey: Optional[int] = None,
+    rotate: Optional[float] = None,
+    scale: Optional[float] = None,
 ) -> None: ...
 def text(x: float, y: float, s: str, col: int, font: Optional[Font] = None) -> None: ...
+def text_rich(
+    x: float,
+    y: float,
+    s: str,
+    col: int,
+    font: Optional[Font] = None,
+    *,
+    time: Optional[int] = None,
+) -> None: ...
+def text_width(s: str, font: Optional[Font] = None) -> int: ...
+def text_bounds(
+    s: str, font: Optional[Font] = None, *, line_spacing: Optional[int] = None
+) -> Tuple[int, int]: ...
+def text_box(
+    x: float,
+    y: float,
+    w: float,
+    h: float,
+    s: str,
+    col: int,
+    font: Optional[Font] = None,
+    *,
+    align: Optional[int] = None,
+    line_spacing: Optional[int] = None,
+    wrap: Optional[bool] = None,
+    ellipsis: Optional[bool] = None,
+) -> None: ...
+def add_glyph(c: str, data: List[str]) -> None: ...
+def add_layer(colkey: Optional[int] = None) -> Layer: ...
+def clear_layers() -> None: ...
+def add_emitter(x: float, y: float) -> Emitter: ...
+def clear_emitters() -> None: ...
+def draw_emitters() -> None: ...
 
 # Audio
 channels: Seq[Channel]
@@ -135,6 +135,10 @@ class App(Widget):
         # Start application
         pyxel.run(self.update_all, self.draw_all)
 
+    @property
+    def resource_file(self):
+        return self._resource_file
+
     @property
     def _editor(self):
         return self._editors[self.editor_type_var]
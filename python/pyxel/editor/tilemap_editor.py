@@ -1,3 +1,5 @@
+import os
+
 import pyxel
 
 from .canvas_panel import CanvasPanel
@@ -144,6 +146,15 @@ class TilemapEditor(EditorBase):
     def __on_update(self):
         self.check_tool_button_shortcuts()
 
+        # Ctrl+E: Export the tilemap as TMX next to the resource file
+        if (pyxel.btn(pyxel.KEY_CTRL) or pyxel.btn(pyxel.KEY_GUI)) and pyxel.btnp(
+            pyxel.KEY_E
+        ):
+            filename = os.path.splitext(self._parent.resource_file)[0]
+            pyxel.tilemaps[self.tilemap_index_var].save_tmx(
+                f"{filename}_tilemap{self.tilemap_index_var}.tmx"
+            )
+
     def __on_draw(self):
         self.draw_panel(11, 156, 136, 17)
         self.draw_panel(157, 156, 72, 17)
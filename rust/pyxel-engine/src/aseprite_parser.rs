@@ -0,0 +1,627 @@
+use std::fs;
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::image::{Color, Image, Rgb24};
+use crate::pyxel::COLORS;
+use crate::settings::MAX_COLORS;
+
+const HEADER_MAGIC: u16 = 0xa5e0;
+const FRAME_MAGIC: u16 = 0xf1fa;
+const HEADER_SIZE: usize = 128;
+const FRAME_HEADER_SIZE: usize = 16;
+const INDEXED_COLOR_DEPTH: u16 = 8;
+
+const OLD_PALETTE_CHUNK: u16 = 0x0004;
+const LAYER_CHUNK: u16 = 0x2004;
+const CEL_CHUNK: u16 = 0x2005;
+const TAGS_CHUNK: u16 = 0x2018;
+const PALETTE_CHUNK: u16 = 0x2019;
+
+const LAYER_VISIBLE: u16 = 1;
+const LAYER_BACKGROUND: u16 = 8;
+const LAYER_TYPE_NORMAL: u16 = 0;
+const LAYER_TYPE_GROUP: u16 = 1;
+const CEL_TYPE_RAW: u16 = 0;
+const CEL_TYPE_LINKED: u16 = 1;
+const CEL_TYPE_COMPRESSED: u16 = 2;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum AsepriteDirection {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+pub struct AsepriteTag {
+    pub name: String,
+    pub from: u32,
+    pub to: u32,
+    pub direction: AsepriteDirection,
+    pub repeat: u32,
+}
+
+pub struct AsepriteFrame {
+    pub u: i32,
+    pub v: i32,
+    pub duration: u32,
+}
+
+pub struct AsepriteData {
+    pub width: u32,
+    pub height: u32,
+    pub frames: Vec<AsepriteFrame>,
+    pub tags: Vec<AsepriteTag>,
+}
+
+struct Layer {
+    name: String,
+    flags: u16,
+    layer_type: u16,
+    child_level: u16,
+    visible: bool,
+}
+
+#[derive(Clone)]
+struct Cel {
+    layer_index: usize,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    data: Vec<Color>,
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + count)
+            .ok_or("Unexpected end of Aseprite file")?;
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn word(&mut self) -> Result<u16, String> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn short(&mut self) -> Result<i16, String> {
+        Ok(self.word()? as i16)
+    }
+
+    fn dword(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.word()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).to_string())
+    }
+}
+
+struct AsepriteFile {
+    width: u32,
+    height: u32,
+    transparent: Color,
+    palette: Vec<Rgb24>,
+    layers: Vec<Layer>,
+    frames: Vec<(u32, Vec<Cel>)>,
+    tags: Vec<AsepriteTag>,
+}
+
+impl AsepriteFile {
+    fn parse(file_data: &[u8]) -> Result<Self, String> {
+        let mut reader = ByteReader::new(file_data);
+        reader.dword()?;
+        if reader.word()? != HEADER_MAGIC {
+            return Err("Not an Aseprite file".to_string());
+        }
+        let num_frames = reader.word()?;
+        let width = reader.word()? as u32;
+        let height = reader.word()? as u32;
+        if reader.word()? != INDEXED_COLOR_DEPTH {
+            return Err("Aseprite file is not in indexed color mode".to_string());
+        }
+        reader.pos = 28;
+        let transparent = reader.byte()?;
+        reader.pos = HEADER_SIZE;
+
+        let mut file = Self {
+            width,
+            height,
+            transparent,
+            palette: Vec::new(),
+            layers: Vec::new(),
+            frames: Vec::new(),
+            tags: Vec::new(),
+        };
+
+        for _ in 0..num_frames {
+            let frame_start = reader.pos;
+            let frame_size = reader.dword()? as usize;
+            if reader.word()? != FRAME_MAGIC {
+                return Err("Invalid frame in Aseprite file".to_string());
+            }
+            let old_num_chunks = reader.word()? as u32;
+            let duration = reader.word()? as u32;
+            reader.bytes(2)?;
+            let num_chunks = match reader.dword()? {
+                0 => old_num_chunks,
+                num_chunks => num_chunks,
+            };
+            reader.pos = frame_start + FRAME_HEADER_SIZE;
+
+            let mut cels = Vec::new();
+            for _ in 0..num_chunks {
+                let chunk_start = reader.pos;
+                let chunk_size = reader.dword()? as usize;
+                let chunk_type = reader.word()?;
+                let chunk_data = reader.bytes(chunk_size.saturating_sub(6))?;
+                let mut chunk = ByteReader::new(chunk_data);
+
+                match chunk_type {
+                    LAYER_CHUNK => {
+                        let layer = file.nest_layer(Self::parse_layer(&mut chunk)?);
+                        file.layers.push(layer);
+                    }
+                    CEL_CHUNK => {
+                        if let Some(cel) = file.parse_cel(&mut chunk)? {
+                            cels.push(cel);
+                        }
+                    }
+                    TAGS_CHUNK => file.tags = Self::parse_tags(&mut chunk)?,
+                    PALETTE_CHUNK => file.parse_palette(&mut chunk)?,
+                    OLD_PALETTE_CHUNK if file.palette.is_empty() => {
+                        file.parse_old_palette(&mut chunk)?;
+                    }
+                    _ => {}
+                }
+                reader.pos = chunk_start + chunk_size;
+            }
+
+            file.frames.push((duration, cels));
+            reader.pos = frame_start + frame_size;
+        }
+
+        Ok(file)
+    }
+
+    fn parse_layer(chunk: &mut ByteReader) -> Result<Layer, String> {
+        let flags = chunk.word()?;
+        let layer_type = chunk.word()?;
+        let child_level = chunk.word()?;
+        chunk.bytes(10)?;
+        let name = chunk.string()?;
+        Ok(Layer {
+            name,
+            flags,
+            layer_type,
+            child_level,
+            visible: flags & LAYER_VISIBLE != 0,
+        })
+    }
+
+    fn nest_layer(&self, mut layer: Layer) -> Layer {
+        // A layer is only shown when every group containing it is shown as well
+        let parent = self
+            .layers
+            .iter()
+            .rev()
+            .find(|parent| parent.child_level < layer.child_level);
+        if let Some(parent) = parent {
+            if parent.layer_type == LAYER_TYPE_GROUP && parent.child_level + 1 == layer.child_level
+            {
+                layer.visible &= parent.visible;
+            }
+        }
+        layer
+    }
+
+    fn parse_cel(&self, chunk: &mut ByteReader) -> Result<Option<Cel>, String> {
+        let layer_index = chunk.word()? as usize;
+        let x = chunk.short()? as i32;
+        let y = chunk.short()? as i32;
+        chunk.byte()?;
+        let cel_type = chunk.word()?;
+        chunk.bytes(7)?;
+
+        let cel = match cel_type {
+            CEL_TYPE_RAW | CEL_TYPE_COMPRESSED => {
+                let width = chunk.word()? as u32;
+                let height = chunk.word()? as u32;
+                let num_pixels = (width * height) as usize;
+                let data = if cel_type == CEL_TYPE_RAW {
+                    chunk.bytes(num_pixels)?.to_vec()
+                } else {
+                    let mut data = Vec::with_capacity(num_pixels);
+                    ZlibDecoder::new(&chunk.data[chunk.pos..])
+                        .read_to_end(&mut data)
+                        .map_err(|_| "Failed to decompress cel in Aseprite file")?;
+                    data
+                };
+                if data.len() < num_pixels {
+                    return Err("Invalid cel in Aseprite file".to_string());
+                }
+                Cel {
+                    layer_index,
+                    x,
+                    y,
+                    width,
+                    height,
+                    data,
+                }
+            }
+            CEL_TYPE_LINKED => {
+                let frame_index = chunk.word()? as usize;
+                let Some((_, cels)) = self.frames.get(frame_index) else {
+                    return Ok(None);
+                };
+                let Some(cel) = cels.iter().find(|cel| cel.layer_index == layer_index) else {
+                    return Ok(None);
+                };
+                cel.clone()
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(cel))
+    }
+
+    fn parse_tags(chunk: &mut ByteReader) -> Result<Vec<AsepriteTag>, String> {
+        let num_tags = chunk.word()?;
+        chunk.bytes(8)?;
+
+        let mut tags = Vec::new();
+        for _ in 0..num_tags {
+            let from = chunk.word()? as u32;
+            let to = chunk.word()? as u32;
+            let direction = match chunk.byte()? {
+                1 => AsepriteDirection::Reverse,
+                2 => AsepriteDirection::PingPong,
+                3 => AsepriteDirection::PingPongReverse,
+                _ => AsepriteDirection::Forward,
+            };
+            let repeat = chunk.word()? as u32;
+            chunk.bytes(10)?;
+            let name = chunk.string()?;
+            tags.push(AsepriteTag {
+                name,
+                from,
+                to,
+                direction,
+                repeat,
+            });
+        }
+
+        Ok(tags)
+    }
+
+    fn parse_palette(&mut self, chunk: &mut ByteReader) -> Result<(), String> {
+        let size = chunk.dword()? as usize;
+        let first = chunk.dword()? as usize;
+        let last = chunk.dword()? as usize;
+        chunk.bytes(8)?;
+
+        self.palette.resize(size.max(self.palette.len()), 0);
+        for index in first..=last {
+            let flags = chunk.word()?;
+            let rgba = chunk.bytes(4)?;
+            if flags & 1 != 0 {
+                chunk.string()?;
+            }
+            if let Some(color) = self.palette.get_mut(index) {
+                *color = ((rgba[0] as Rgb24) << 16) | ((rgba[1] as Rgb24) << 8) | rgba[2] as Rgb24;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_old_palette(&mut self, chunk: &mut ByteReader) -> Result<(), String> {
+        let num_packets = chunk.word()?;
+        let mut index = 0;
+        for _ in 0..num_packets {
+            index += chunk.byte()? as usize;
+            let num_colors = match chunk.byte()? {
+                0 => 256,
+                num_colors => num_colors as usize,
+            };
+            for _ in 0..num_colors {
+                let rgb = chunk.bytes(3)?;
+                if self.palette.len() <= index {
+                    self.palette.resize(index + 1, 0);
+                }
+                self.palette[index] =
+                    ((rgb[0] as Rgb24) << 16) | ((rgb[1] as Rgb24) << 8) | rgb[2] as Rgb24;
+                index += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render_frame(&self, frame_index: usize, layer_index: Option<usize>) -> Vec<Color> {
+        let mut data = vec![self.transparent; (self.width * self.height) as usize];
+        let (_, cels) = &self.frames[frame_index];
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            let is_selected = layer_index.map_or(layer.visible, |layer_index| layer_index == index);
+            if !is_selected || layer.layer_type != LAYER_TYPE_NORMAL {
+                continue;
+            }
+
+            // Background layers are opaque, other layers skip the transparent index
+            let is_background = layer.flags & LAYER_BACKGROUND != 0;
+            for cel in cels.iter().filter(|cel| cel.layer_index == index) {
+                for cy in 0..cel.height as i32 {
+                    for cx in 0..cel.width as i32 {
+                        let (x, y) = (cel.x + cx, cel.y + cy);
+                        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+                            continue;
+                        }
+                        let color = cel.data[(cy * cel.width as i32 + cx) as usize];
+                        if is_background || color != self.transparent {
+                            data[(y * self.width as i32 + x) as usize] = color;
+                        }
+                    }
+                }
+            }
+        }
+
+        data
+    }
+}
+
+impl Image {
+    pub fn load_aseprite(
+        &mut self,
+        x: i32,
+        y: i32,
+        filename: &str,
+        layer: Option<&str>,
+        columns: Option<u32>,
+        include_colors: Option<bool>,
+    ) -> Result<AsepriteData, String> {
+        let file_data =
+            fs::read(filename).map_err(|_| format!("Failed to open file '{filename}'"))?;
+        let file = AsepriteFile::parse(&file_data)?;
+
+        let layer_index = layer
+            .map(|name| {
+                file.layers
+                    .iter()
+                    .position(|layer| layer.name == name)
+                    .ok_or_else(|| format!("Layer '{name}' not found in Aseprite file"))
+            })
+            .transpose()?;
+
+        // Pyxel colors stop one short of the 256 Aseprite palette entries
+        let frames_data: Vec<_> = (0..file.frames.len())
+            .map(|frame_index| file.render_frame(frame_index, layer_index))
+            .collect();
+        if let Some(color) = frames_data
+            .iter()
+            .flatten()
+            .find(|&&color| color as u32 >= MAX_COLORS)
+        {
+            return Err(format!(
+                "Color index {color} in Aseprite file exceeds {}",
+                MAX_COLORS - 1
+            ));
+        }
+
+        let columns = columns
+            .unwrap_or_else(|| (self.width() as i32 - x).max(0) as u32 / file.width.max(1))
+            .max(1);
+        if include_colors.unwrap_or(false) {
+            let mut colors = COLORS.lock();
+            colors.clear();
+            colors.extend(file.palette.iter().take(MAX_COLORS as usize));
+        }
+
+        let mut frames = Vec::new();
+        for (frame_index, ((duration, _), data)) in file.frames.iter().zip(&frames_data).enumerate()
+        {
+            let u = x + (frame_index as u32 % columns * file.width) as i32;
+            let v = y + (frame_index as u32 / columns * file.height) as i32;
+
+            // Frames are copied as is without the stencil or dithering of the image
+            let width = self.width() as i32;
+            for fy in 0..file.height {
+                for fx in 0..file.width {
+                    let (px, py) = (u + fx as i32, v + fy as i32);
+                    if self.canvas.clip_rect.contains(px, py) {
+                        self.canvas.data[(py * width + px) as usize] =
+                            data[(fy * file.width + fx) as usize];
+                    }
+                }
+            }
+
+            frames.push(AsepriteFrame {
+                u,
+                v,
+                duration: *duration,
+            });
+        }
+
+        Ok(AsepriteData {
+            width: file.width,
+            height: file.height,
+            frames,
+            tags: file.tags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::StencilMode;
+
+    fn chunk(chunk_type: u16, data: &[u8]) -> Vec<u8> {
+        let mut chunk = ((data.len() + 6) as u32).to_le_bytes().to_vec();
+        chunk.extend(chunk_type.to_le_bytes());
+        chunk.extend(data);
+        chunk
+    }
+
+    fn frame(duration: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        let data = chunks.concat();
+        let mut frame = ((data.len() + FRAME_HEADER_SIZE) as u32)
+            .to_le_bytes()
+            .to_vec();
+        frame.extend(FRAME_MAGIC.to_le_bytes());
+        frame.extend((chunks.len() as u16).to_le_bytes());
+        frame.extend(duration.to_le_bytes());
+        frame.extend([0; 6]);
+        frame.extend(data);
+        frame
+    }
+
+    fn layer(flags: u16, layer_type: u16, child_level: u16, name: &str) -> Vec<u8> {
+        let mut layer = flags.to_le_bytes().to_vec();
+        layer.extend(layer_type.to_le_bytes());
+        layer.extend(child_level.to_le_bytes());
+        layer.extend([0; 10]);
+        layer.extend((name.len() as u16).to_le_bytes());
+        layer.extend(name.as_bytes());
+        layer
+    }
+
+    fn aseprite_data() -> Vec<u8> {
+        aseprite_file(&[layer(LAYER_VISIBLE, LAYER_TYPE_NORMAL, 0, "base")], 0, 5)
+    }
+
+    fn aseprite_file(layers: &[Vec<u8>], cel_layer: u8, color: u8) -> Vec<u8> {
+        let mut raw_cel = vec![cel_layer, 0, 1, 0, 0, 0, 255, 0, 0];
+        raw_cel.extend([0; 7]);
+        raw_cel.extend([2, 0, 1, 0, color, 0]);
+        let mut linked_cel = vec![cel_layer, 0, 1, 0, 0, 0, 255, 1, 0];
+        linked_cel.extend([0; 7]);
+        linked_cel.extend([0, 0]);
+
+        let mut palette = vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0];
+        palette.extend([0; 8]);
+        palette.extend([0, 0, 1, 2, 3, 255, 0, 0, 255, 0, 0, 255]);
+
+        let mut tags = vec![1, 0];
+        tags.extend([0; 8]);
+        tags.extend([0, 0, 1, 0, 2, 0, 0]);
+        tags.extend([0; 10]);
+        tags.extend([4, 0]);
+        tags.extend(b"walk");
+
+        let mut chunks: Vec<_> = layers
+            .iter()
+            .map(|layer| chunk(LAYER_CHUNK, layer))
+            .collect();
+        chunks.extend([
+            chunk(PALETTE_CHUNK, &palette),
+            chunk(TAGS_CHUNK, &tags),
+            chunk(CEL_CHUNK, &raw_cel),
+        ]);
+        let frames = [
+            frame(100, &chunks),
+            frame(50, &[chunk(CEL_CHUNK, &linked_cel)]),
+        ]
+        .concat();
+
+        let mut header = vec![0; HEADER_SIZE];
+        header[4..6].copy_from_slice(&HEADER_MAGIC.to_le_bytes());
+        header[6..8].copy_from_slice(&2_u16.to_le_bytes());
+        header[8..10].copy_from_slice(&3_u16.to_le_bytes());
+        header[10..12].copy_from_slice(&2_u16.to_le_bytes());
+        header[12..14].copy_from_slice(&INDEXED_COLOR_DEPTH.to_le_bytes());
+        header.extend(frames);
+        header
+    }
+
+    #[test]
+    fn test_parse() {
+        let file = AsepriteFile::parse(&aseprite_data()).unwrap();
+        assert_eq!((file.width, file.height), (3, 2));
+        assert_eq!(file.palette, vec![0x010203, 0xff0000]);
+        assert_eq!(file.layers[0].name, "base");
+        assert_eq!(file.frames.len(), 2);
+        assert_eq!(file.frames[1].0, 50);
+        assert_eq!(file.tags[0].name, "walk");
+        assert_eq!(file.tags[0].direction, AsepriteDirection::PingPong);
+        assert_eq!(file.render_frame(0, None), vec![0, 5, 0, 0, 0, 0]);
+        assert_eq!(file.render_frame(1, None), vec![0, 5, 0, 0, 0, 0]);
+        assert!(AsepriteFile::parse(&[0; 8]).is_err());
+    }
+
+    #[test]
+    fn test_hidden_group() {
+        let data = aseprite_file(
+            &[
+                layer(0, LAYER_TYPE_GROUP, 0, "group"),
+                layer(LAYER_VISIBLE, LAYER_TYPE_NORMAL, 1, "child"),
+                layer(LAYER_VISIBLE, LAYER_TYPE_NORMAL, 0, "top"),
+            ],
+            1,
+            5,
+        );
+        let file = AsepriteFile::parse(&data).unwrap();
+        assert_eq!(
+            file.layers
+                .iter()
+                .map(|layer| layer.visible)
+                .collect::<Vec<_>>(),
+            vec![false, false, true]
+        );
+        assert_eq!(file.render_frame(0, None), vec![0; 6]);
+        assert_eq!(file.render_frame(0, Some(1)), vec![0, 5, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_load_aseprite() {
+        let filename = std::env::temp_dir().join("pyxel_aseprite_load_test.aseprite");
+        let filename = filename.to_str().unwrap();
+
+        fs::write(filename, aseprite_data()).unwrap();
+        let image = Image::new(8, 8);
+        let mut image = image.lock();
+        image.canvas.stencil_fill(false);
+        image.canvas.set_stencil_mode(StencilMode::Test);
+        let data = image
+            .load_aseprite(0, 0, filename, None, Some(2), None)
+            .unwrap();
+        assert_eq!(data.frames.len(), 2);
+        assert_eq!((data.frames[1].u, data.frames[1].v), (3, 0));
+        assert_eq!(image.canvas.data[1], 5);
+        assert_eq!(image.canvas.data[4], 5);
+
+        fs::write(
+            filename,
+            aseprite_file(
+                &[layer(LAYER_VISIBLE, LAYER_TYPE_NORMAL, 0, "base")],
+                0,
+                255,
+            ),
+        )
+        .unwrap();
+        assert!(image
+            .load_aseprite(0, 0, filename, None, None, None)
+            .is_err());
+        fs::remove_file(filename).unwrap();
+    }
+}
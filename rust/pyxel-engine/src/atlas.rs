@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::image::{Color, Image, SharedImage};
+use crate::pyxel::{Pyxel, IMAGES};
+use crate::settings::{IMAGE_SIZE, NUM_IMAGES};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct AtlasEntry {
+    pub bank: u32,
+    pub u: u32,
+    pub v: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Atlas {
+    pub entries: BTreeMap<String, AtlasEntry>,
+}
+
+struct Skyline {
+    segments: Vec<(u32, u32, u32)>,
+}
+
+impl Skyline {
+    fn new() -> Self {
+        Self {
+            segments: vec![(0, 0, IMAGE_SIZE)],
+        }
+    }
+
+    fn find_position(&self, width: u32, height: u32) -> Option<(usize, u32, u32)> {
+        let mut best: Option<(usize, u32, u32)> = None;
+
+        for (index, &(x, _, _)) in self.segments.iter().enumerate() {
+            if x + width > IMAGE_SIZE {
+                break;
+            }
+
+            let mut y = 0;
+            let mut covered = 0;
+            for &(_, segment_y, segment_width) in &self.segments[index..] {
+                y = y.max(segment_y);
+                covered += segment_width;
+                if covered >= width {
+                    break;
+                }
+            }
+
+            if y + height <= IMAGE_SIZE && best.is_none_or(|(_, _, best_y)| y < best_y) {
+                best = Some((index, x, y));
+            }
+        }
+
+        best
+    }
+
+    fn place(&mut self, index: usize, x: u32, y: u32, width: u32, height: u32) {
+        self.segments.insert(index, (x, y + height, width));
+
+        // Segments hidden under the new one are trimmed or removed
+        let right = x + width;
+        let i = index + 1;
+        while i < self.segments.len() {
+            let (segment_x, segment_y, segment_width) = self.segments[i];
+            if segment_x >= right {
+                break;
+            }
+            let segment_right = segment_x + segment_width;
+            if segment_right <= right {
+                self.segments.remove(i);
+            } else {
+                self.segments[i] = (right, segment_y, segment_right - right);
+                break;
+            }
+        }
+
+        // Neighbouring segments at the same height are merged
+        let mut i = 0;
+        while i + 1 < self.segments.len() {
+            if self.segments[i].1 == self.segments[i + 1].1 {
+                self.segments[i].2 += self.segments[i + 1].2;
+                self.segments.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+impl Atlas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pack(images: &[(String, SharedImage)], bank: Option<u32>) -> Result<Self, String> {
+        let sizes: Vec<(u32, u32)> = images
+            .iter()
+            .map(|(_, image)| {
+                let image = image.lock();
+                (image.width(), image.height())
+            })
+            .collect();
+        let positions = Self::pack_rects(&sizes, bank.unwrap_or(0))?;
+
+        let banks = IMAGES.lock();
+        let mut atlas = Self::new();
+        for ((name, image), (bank, u, v)) in images.iter().zip(positions) {
+            // Pixels are copied as is regardless of the drawing state of the bank
+            let (w, h, data) = {
+                let image = image.lock();
+                (image.width(), image.height(), image.canvas.data.clone())
+            };
+            let mut bank_image = banks[bank as usize].lock();
+            let stride = bank_image.width() as usize;
+            for y in 0..h as usize {
+                let offset = (v as usize + y) * stride + u as usize;
+                bank_image.canvas.data[offset..offset + w as usize]
+                    .copy_from_slice(&data[y * w as usize..(y + 1) * w as usize]);
+            }
+            atlas
+                .entries
+                .insert(name.clone(), AtlasEntry { bank, u, v, w, h });
+        }
+
+        Ok(atlas)
+    }
+
+    pub fn pack_dir(dirname: &str, bank: Option<u32>) -> Result<Self, String> {
+        let mut filenames: Vec<_> = fs::read_dir(dirname)
+            .map_err(|_| format!("Failed to open directory '{dirname}'"))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+            })
+            .collect();
+        filenames.sort();
+
+        let images: Vec<(String, SharedImage)> = filenames
+            .iter()
+            .map(|path| {
+                let name = path
+                    .file_stem()
+                    .map_or_else(String::new, |stem| stem.to_string_lossy().to_string());
+                let image =
+                    Image::from_image(&path.to_string_lossy(), None, None, None, None, None);
+                (name, image)
+            })
+            .collect();
+
+        Self::pack(&images, bank)
+    }
+
+    pub fn load(filename: &str) -> Result<Self, String> {
+        let toml_text = fs::read_to_string(filename)
+            .map_err(|_| format!("Failed to open file '{filename}'"))?;
+        toml::from_str(&toml_text).map_err(|_| format!("Failed to parse atlas file '{filename}'"))
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        let toml_text = toml::to_string(self).map_err(|_| "Failed to serialize atlas")?;
+        fs::write(Path::new(filename), toml_text)
+            .map_err(|_| format!("Failed to open file '{filename}'"))
+    }
+
+    pub fn get(&self, name: &str) -> Option<AtlasEntry> {
+        self.entries.get(name).copied()
+    }
+
+    fn pack_rects(sizes: &[(u32, u32)], first_bank: u32) -> Result<Vec<(u32, u32, u32)>, String> {
+        // Taller images are placed first as they are the hardest to fit
+        let mut order: Vec<usize> = (0..sizes.len()).collect();
+        order.sort_by_key(|&i| (std::cmp::Reverse(sizes[i].1), std::cmp::Reverse(sizes[i].0)));
+
+        let mut skylines: Vec<Skyline> = Vec::new();
+        let mut positions = vec![(0, 0, 0); sizes.len()];
+        for i in order {
+            let (width, height) = sizes[i];
+            if width > IMAGE_SIZE || height > IMAGE_SIZE {
+                return Err(format!(
+                    "Image larger than {IMAGE_SIZE}x{IMAGE_SIZE} cannot be packed"
+                ));
+            }
+
+            let mut placed = false;
+            for (bank_offset, skyline) in skylines.iter_mut().enumerate() {
+                if let Some((index, x, y)) = skyline.find_position(width, height) {
+                    skyline.place(index, x, y, width, height);
+                    positions[i] = (first_bank + bank_offset as u32, x, y);
+                    placed = true;
+                    break;
+                }
+            }
+            if !placed {
+                let bank = first_bank + skylines.len() as u32;
+                if bank >= NUM_IMAGES {
+                    return Err("Images do not fit in the image banks".to_string());
+                }
+                let mut skyline = Skyline::new();
+                skyline.place(0, 0, 0, width, height);
+                skylines.push(skyline);
+                positions[i] = (bank, 0, 0);
+            }
+        }
+
+        Ok(positions)
+    }
+}
+
+impl Pyxel {
+    pub fn blt_sprite(
+        &self,
+        x: f64,
+        y: f64,
+        name: &str,
+        color_key: Option<Color>,
+        rotate: Option<f64>,
+        scale: Option<f64>,
+    ) -> Result<(), String> {
+        let entry = self
+            .atlas
+            .get(name)
+            .ok_or_else(|| format!("Sprite '{name}' not found in atlas"))?;
+        self.blt(
+            x,
+            y,
+            entry.bank,
+            entry.u as f64,
+            entry.v as f64,
+            entry.w as f64,
+            entry.h as f64,
+            color_key,
+            rotate,
+            scale,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_rects() {
+        let sizes = [(100, 50), (200, 100), (60, 60), (256, 200), (30, 10)];
+        let positions = Atlas::pack_rects(&sizes, 0).unwrap();
+
+        for (i, &(bank, u, v)) in positions.iter().enumerate() {
+            let (w, h) = sizes[i];
+            assert!(u + w <= IMAGE_SIZE && v + h <= IMAGE_SIZE);
+            for (j, &(other_bank, other_u, other_v)) in positions.iter().enumerate().skip(i + 1) {
+                let (other_w, other_h) = sizes[j];
+                let overlaps = bank == other_bank
+                    && u < other_u + other_w
+                    && other_u < u + w
+                    && v < other_v + other_h
+                    && other_v < v + h;
+                assert!(!overlaps);
+            }
+        }
+        assert_eq!(positions[3], (0, 0, 0));
+        assert_eq!(positions[1].0, 1);
+
+        assert!(Atlas::pack_rects(&[(300, 10)], 0).is_err());
+        assert!(Atlas::pack_rects(&[(256, 256)], NUM_IMAGES).is_err());
+    }
+
+    #[test]
+    fn test_pack_ignores_draw_state() {
+        let image = Image::new(2, 2);
+        image.lock().canvas.data.copy_from_slice(&[1, 2, 3, 4]);
+
+        let bank = IMAGES.lock()[NUM_IMAGES as usize - 1].clone();
+        bank.lock().camera(1.0, 1.0);
+        bank.lock().canvas.dither(0.0);
+        let atlas = Atlas::pack(&[("a".to_string(), image)], Some(NUM_IMAGES - 1)).unwrap();
+        let entry = atlas.get("a").unwrap();
+        let mut bank = bank.lock();
+        bank.camera0();
+        bank.canvas.dither(1.0);
+        let stride = bank.width() as usize;
+        let offset = entry.v as usize * stride + entry.u as usize;
+        assert_eq!(bank.canvas.data[offset..offset + 2], [1, 2]);
+        assert_eq!(
+            bank.canvas.data[offset + stride..offset + stride + 2],
+            [3, 4]
+        );
+    }
+}
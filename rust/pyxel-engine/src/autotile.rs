@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use crate::settings::AUTOTILE_BLOB47;
+use crate::tilemap::{ImageTileCoord, Tile, Tilemap};
+
+const NORTH: u8 = 1;
+const NORTH_EAST: u8 = 2;
+const EAST: u8 = 4;
+const SOUTH_EAST: u8 = 8;
+const SOUTH: u8 = 16;
+const SOUTH_WEST: u8 = 32;
+const WEST: u8 = 64;
+const NORTH_WEST: u8 = 128;
+
+const NEIGHBOR_BITS: [(i32, i32, u8); 8] = [
+    (0, -1, NORTH),
+    (1, -1, NORTH_EAST),
+    (1, 0, EAST),
+    (1, 1, SOUTH_EAST),
+    (0, 1, SOUTH),
+    (-1, 1, SOUTH_WEST),
+    (-1, 0, WEST),
+    (-1, -1, NORTH_WEST),
+];
+
+// Masks of the 7x7 blob template by cr31 (Wang blob tiles), whose neighboring cells connect
+// Cells with -1 are left empty in the template
+const BLOB47_TEMPLATE: [[i16; 7]; 7] = [
+    [0, 4, 92, 124, 116, 80, -1],
+    [16, 20, 87, 223, 241, 21, 64],
+    [29, 117, 85, 71, 221, 125, 112],
+    [31, 253, 113, 28, 127, 247, 209],
+    [23, 199, 213, 95, 255, 245, 81],
+    [5, 84, 93, 119, 215, 193, 17],
+    [-1, 1, 7, 197, 69, 68, 65],
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AutotileLayout {
+    Blob16,
+    Blob47,
+}
+
+pub struct Terrain {
+    pub layout: AutotileLayout,
+    pub tiles: HashMap<u8, Tile>,
+}
+
+impl AutotileLayout {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            AUTOTILE_BLOB47 => Self::Blob47,
+            _ => Self::Blob16,
+        }
+    }
+
+    pub fn masks(self) -> Vec<u8> {
+        match self {
+            Self::Blob16 => (0..16).collect(),
+            Self::Blob47 => (0..=255)
+                .filter(|&mask| Self::reduce_blob47(mask) == mask)
+                .collect(),
+        }
+    }
+
+    pub fn mask(self, neighbors: u8) -> u8 {
+        match self {
+            // Edges only, packed as N=1, E=2, S=4, W=8
+            Self::Blob16 => {
+                u8::from(neighbors & NORTH != 0)
+                    | (u8::from(neighbors & EAST != 0) << 1)
+                    | (u8::from(neighbors & SOUTH != 0) << 2)
+                    | (u8::from(neighbors & WEST != 0) << 3)
+            }
+            Self::Blob47 => Self::reduce_blob47(neighbors),
+        }
+    }
+
+    fn reduce_blob47(neighbors: u8) -> u8 {
+        // A corner only counts when both of its adjacent edges are connected
+        let mut mask = neighbors & (NORTH | EAST | SOUTH | WEST);
+        for (corner, edge1, edge2) in [
+            (NORTH_EAST, NORTH, EAST),
+            (SOUTH_EAST, SOUTH, EAST),
+            (SOUTH_WEST, SOUTH, WEST),
+            (NORTH_WEST, NORTH, WEST),
+        ] {
+            if neighbors & corner != 0 && neighbors & edge1 != 0 && neighbors & edge2 != 0 {
+                mask |= corner;
+            }
+        }
+        mask
+    }
+}
+
+impl Terrain {
+    pub fn new(layout: AutotileLayout) -> Self {
+        Self {
+            layout,
+            tiles: HashMap::new(),
+        }
+    }
+
+    pub fn from_sheet(
+        layout: AutotileLayout,
+        tile_x: ImageTileCoord,
+        tile_y: ImageTileCoord,
+        columns: Option<u32>,
+    ) -> Result<Self, String> {
+        // The whole sheet must fit in the tile coordinates of the image
+        let sheet_tile = |column: u32, row: u32| -> Result<Tile, String> {
+            let x = ImageTileCoord::try_from(tile_x as u32 + column);
+            let y = ImageTileCoord::try_from(tile_y as u32 + row);
+            match (x, y) {
+                (Ok(x), Ok(y)) => Ok((x, y)),
+                _ => Err(format!(
+                    "Autotile sheet at ({tile_x}, {tile_y}) exceeds the tile coordinates"
+                )),
+            }
+        };
+
+        let mut terrain = Self::new(layout);
+        match layout {
+            // Blob16 tiles are laid out in ascending mask order, row by row
+            AutotileLayout::Blob16 => {
+                let columns = columns.unwrap_or(4).max(1);
+                for (i, mask) in layout.masks().into_iter().enumerate() {
+                    let i = i as u32;
+                    terrain.set_tile(mask, sheet_tile(i % columns, i / columns)?);
+                }
+            }
+
+            // Blob47 tiles follow the fixed 7x7 template, so columns are ignored
+            AutotileLayout::Blob47 => {
+                for (row, masks) in BLOB47_TEMPLATE.iter().enumerate() {
+                    for (column, &mask) in masks.iter().enumerate() {
+                        if let Ok(mask) = u8::try_from(mask) {
+                            terrain.set_tile(mask, sheet_tile(column as u32, row as u32)?);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(terrain)
+    }
+
+    pub fn set_tile(&mut self, mask: u8, tile: Tile) {
+        self.tiles.insert(mask, tile);
+    }
+
+    pub fn tile(&self, mask: u8) -> Option<Tile> {
+        self.tiles.get(&mask).copied()
+    }
+
+    pub fn contains(&self, tile: Tile) -> bool {
+        self.tiles
+            .values()
+            .any(|&terrain_tile| terrain_tile == tile)
+    }
+
+    pub fn default_tile(&self) -> Option<Tile> {
+        let full_mask = self.layout.mask(u8::MAX);
+        self.tile(full_mask).or_else(|| {
+            self.tiles
+                .iter()
+                .min_by_key(|(mask, _)| **mask)
+                .map(|(_, tile)| *tile)
+        })
+    }
+}
+
+impl Tilemap {
+    pub fn autotile(&mut self, x: i32, y: i32, width: u32, height: u32, terrain: &Terrain) {
+        for tile_y in y..y + height as i32 {
+            for tile_x in x..x + width as i32 {
+                if !self.is_terrain(tile_x, tile_y, terrain) {
+                    continue;
+                }
+                let mask = terrain
+                    .layout
+                    .mask(self.terrain_neighbors(tile_x, tile_y, terrain));
+                if let Some(tile) = terrain.tile(mask) {
+                    self.canvas
+                        .write_data(tile_x as usize, tile_y as usize, tile);
+                }
+            }
+        }
+    }
+
+    pub fn pset_terrain(&mut self, x: i32, y: i32, terrain: &Terrain) {
+        if x < 0 || y < 0 || x >= self.width() as i32 || y >= self.height() as i32 {
+            return;
+        }
+        let Some(tile) = terrain.default_tile() else {
+            return;
+        };
+        self.canvas.write_data(x as usize, y as usize, tile);
+        self.autotile(x - 1, y - 1, 3, 3, terrain);
+    }
+
+    fn is_terrain(&self, x: i32, y: i32, terrain: &Terrain) -> bool {
+        x >= 0
+            && y >= 0
+            && x < self.width() as i32
+            && y < self.height() as i32
+            && terrain.contains(self.canvas.read_data(x as usize, y as usize))
+    }
+
+    fn terrain_neighbors(&self, x: i32, y: i32, terrain: &Terrain) -> u8 {
+        // Neighbors outside the tilemap are treated as connected
+        NEIGHBOR_BITS
+            .iter()
+            .filter(|&&(dx, dy, _)| {
+                let (nx, ny) = (x + dx, y + dy);
+                nx < 0
+                    || ny < 0
+                    || nx >= self.width() as i32
+                    || ny >= self.height() as i32
+                    || self.is_terrain(nx, ny, terrain)
+            })
+            .fold(0, |mask, &(_, _, bit)| mask | bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::ImageSource;
+
+    #[test]
+    fn test_layout_masks() {
+        assert_eq!(AutotileLayout::Blob16.masks().len(), 16);
+        assert_eq!(AutotileLayout::Blob47.masks().len(), 47);
+        assert_eq!(AutotileLayout::Blob47.mask(NORTH_EAST), 0);
+        assert_eq!(
+            AutotileLayout::Blob47.mask(NORTH | NORTH_EAST | EAST),
+            NORTH | NORTH_EAST | EAST
+        );
+        assert_eq!(AutotileLayout::Blob16.mask(NORTH | EAST | NORTH_EAST), 3);
+    }
+
+    #[test]
+    fn test_blob47_template() {
+        let terrain = Terrain::from_sheet(AutotileLayout::Blob47, 0, 8, None).unwrap();
+        assert_eq!(terrain.tiles.len(), 47);
+        assert!(AutotileLayout::Blob47
+            .masks()
+            .iter()
+            .all(|&mask| terrain.tile(mask).is_some()));
+        assert_eq!(terrain.tile(0), Some((0, 8)));
+        assert_eq!(terrain.tile(u8::MAX), Some((4, 12)));
+        assert_eq!(terrain.tile(NORTH), Some((1, 14)));
+        assert!(Terrain::from_sheet(AutotileLayout::Blob47, 250, 0, None).is_err());
+        assert!(Terrain::from_sheet(AutotileLayout::Blob16, 0, 252, None).is_ok());
+        assert!(Terrain::from_sheet(AutotileLayout::Blob16, 0, 253, None).is_err());
+        assert!(Terrain::from_sheet(AutotileLayout::Blob16, 250, 0, Some(300)).is_err());
+
+        // Adjacent cells of the template connect to each other
+        for (row, masks) in BLOB47_TEMPLATE.iter().enumerate() {
+            for (column, &mask) in masks.iter().enumerate() {
+                let right = masks.get(column + 1).copied().unwrap_or(-1);
+                if mask >= 0 && right >= 0 {
+                    assert_eq!(mask & EAST as i16 != 0, right & WEST as i16 != 0);
+                }
+                let below = BLOB47_TEMPLATE
+                    .get(row + 1)
+                    .map_or(-1, |masks| masks[column]);
+                if mask >= 0 && below >= 0 {
+                    assert_eq!(mask & SOUTH as i16 != 0, below & NORTH as i16 != 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pset_terrain() {
+        let terrain = Terrain::from_sheet(AutotileLayout::Blob16, 0, 8, None).unwrap();
+        let tilemap = Tilemap::new(5, 5, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.cls((1, 1));
+
+        tilemap.pset_terrain(2, 2, &terrain);
+        assert_eq!(tilemap.pget(2.0, 2.0), (0, 8));
+
+        tilemap.pset_terrain(3, 2, &terrain);
+        assert_eq!(tilemap.pget(2.0, 2.0), (2, 8));
+        assert_eq!(tilemap.pget(3.0, 2.0), (0, 10));
+
+        // Edges of the tilemap connect to the outside
+        tilemap.pset_terrain(0, 0, &terrain);
+        assert_eq!(tilemap.pget(0.0, 0.0), (1, 10));
+    }
+}
@@ -0,0 +1,303 @@
+use rand::Rng;
+use rand_xoshiro::Xoshiro256StarStar;
+
+use crate::canvas::StencilMode;
+use crate::image::{Color, Image};
+use crate::math::new_rng;
+use crate::pyxel::Pyxel;
+use crate::settings::TILE_SIZE;
+use crate::tilemap::Tilemap;
+
+const MIN_SHAKE_INTENSITY: f64 = 0.1;
+
+pub struct Camera {
+    pub enabled: bool,
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+    pub smoothing: f64,
+    pub deadzone_width: f64,
+    pub deadzone_height: f64,
+    pub bounds: Option<(f64, f64, f64, f64)>,
+    pub shake_x: f64,
+    pub shake_y: f64,
+    target: Option<(f64, f64)>,
+    shake_intensity: f64,
+    shake_decay: f64,
+    rng: Xoshiro256StarStar,
+    pub(crate) zoomed: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            x: 0.0,
+            y: 0.0,
+            zoom: 1.0,
+            smoothing: 1.0,
+            deadzone_width: 0.0,
+            deadzone_height: 0.0,
+            bounds: None,
+            shake_x: 0.0,
+            shake_y: 0.0,
+            target: None,
+            shake_intensity: 0.0,
+            shake_decay: 0.0,
+            rng: new_rng(),
+            zoomed: false,
+        }
+    }
+
+    pub fn follow(&mut self, x: f64, y: f64) {
+        self.enabled = true;
+        self.target = Some((x, y));
+    }
+
+    pub fn unfollow(&mut self) {
+        self.target = None;
+    }
+
+    pub fn disable(&mut self) {
+        self.enabled = false;
+        self.target = None;
+        self.shake_intensity = 0.0;
+        self.shake_x = 0.0;
+        self.shake_y = 0.0;
+    }
+
+    pub fn look_at(&mut self, x: f64, y: f64) {
+        self.enabled = true;
+        self.target = None;
+        self.x = x;
+        self.y = y;
+    }
+
+    pub fn set_deadzone(&mut self, width: f64, height: f64) {
+        self.deadzone_width = width.max(0.0);
+        self.deadzone_height = height.max(0.0);
+    }
+
+    pub fn set_bounds(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.bounds = Some((x, y, width, height));
+    }
+
+    pub fn clamp_to_tilemap(&mut self, tilemap: &Tilemap) {
+        let tile_size = TILE_SIZE as f64;
+        self.set_bounds(
+            0.0,
+            0.0,
+            tilemap.width() as f64 * tile_size,
+            tilemap.height() as f64 * tile_size,
+        );
+    }
+
+    pub fn shake(&mut self, intensity: f64, decay: Option<f64>) {
+        self.shake_intensity = self.shake_intensity.max(intensity);
+        self.shake_decay = decay.unwrap_or(0.9).clamp(0.0, 1.0);
+    }
+
+    pub fn update(&mut self, view_width: f64, view_height: f64) {
+        let zoom = self.zoom.max(f64::EPSILON);
+
+        if let Some((target_x, target_y)) = self.target {
+            // The camera only moves once the target leaves the deadzone
+            let follow_axis = |pos: f64, target: f64, deadzone: f64| {
+                let half = deadzone / zoom / 2.0;
+                if target < pos - half {
+                    target + half
+                } else if target > pos + half {
+                    target - half
+                } else {
+                    pos
+                }
+            };
+            let goal_x = follow_axis(self.x, target_x, self.deadzone_width);
+            let goal_y = follow_axis(self.y, target_y, self.deadzone_height);
+            let smoothing = self.smoothing.clamp(0.0, 1.0);
+            self.x += (goal_x - self.x) * smoothing;
+            self.y += (goal_y - self.y) * smoothing;
+        }
+
+        if let Some((left, top, width, height)) = self.bounds {
+            let clamp_axis = |pos: f64, start: f64, size: f64, view_size: f64| {
+                let half = view_size / zoom / 2.0;
+                if size <= half * 2.0 {
+                    start + size / 2.0
+                } else {
+                    pos.clamp(start + half, start + size - half)
+                }
+            };
+            self.x = clamp_axis(self.x, left, width, view_width);
+            self.y = clamp_axis(self.y, top, height, view_height);
+        }
+
+        if self.shake_intensity >= MIN_SHAKE_INTENSITY {
+            // Shaking uses its own RNG so as not to disturb the sequence of rndi/rndf
+            let intensity = self.shake_intensity;
+            self.shake_x = self.rng.random_range(-intensity..=intensity);
+            self.shake_y = self.rng.random_range(-intensity..=intensity);
+            self.shake_intensity *= self.shake_decay;
+        } else {
+            self.shake_intensity = 0.0;
+            self.shake_x = 0.0;
+            self.shake_y = 0.0;
+        }
+    }
+
+    pub fn origin(&self, view_width: f64, view_height: f64) -> (f64, f64) {
+        (
+            (self.x - view_width / 2.0 + self.shake_x).round(),
+            (self.y - view_height / 2.0 + self.shake_y).round(),
+        )
+    }
+}
+
+impl Pyxel {
+    pub fn bltm_camera(&self, tilemap_index: u32, color_key: Option<Color>) {
+        if !self.view_camera.enabled {
+            return;
+        }
+
+        let (width, height) = (self.width as f64, self.height as f64);
+        let (x, y) = self.view_camera.origin(width, height);
+        let tilemap = self.tilemaps.lock()[tilemap_index as usize].clone();
+        self.screen.lock().bltm(
+            x,
+            y,
+            tilemap,
+            x,
+            y,
+            width,
+            height,
+            color_key,
+            None,
+            None,
+            Some(self.frame_count),
+        );
+    }
+
+    pub(crate) fn update_view_camera(&mut self) {
+        if !self.view_camera.enabled {
+            return;
+        }
+
+        let (width, height) = (self.width as f64, self.height as f64);
+        self.view_camera.update(width, height);
+        let (x, y) = self.view_camera.origin(width, height);
+        self.screen.lock().camera(x, y);
+    }
+
+    pub fn disable_view_camera(&mut self) {
+        self.view_camera.disable();
+        self.screen.lock().camera0();
+    }
+
+    // Zooms the screen drawn so far, at most once per frame
+    // Calling this after drawing the world keeps UI drawn afterwards unscaled
+    // Otherwise the whole frame is zoomed at the end of drawing
+    pub fn zoom_view_camera(&mut self) {
+        let zoom = self.view_camera.zoom;
+        if !self.view_camera.enabled || self.view_camera.zoomed || (zoom - 1.0).abs() < f64::EPSILON
+        {
+            return;
+        }
+        self.view_camera.zoomed = true;
+
+        // The screen drawn so far is magnified around its center
+        let frame = Image::new(self.width, self.height);
+        let mut screen = self.screen.lock();
+        frame.lock().canvas.data.clone_from(&screen.canvas.data);
+
+        let clip_rect = screen.canvas.clip_rect;
+        let camera_x = screen.canvas.camera_x;
+        let camera_y = screen.canvas.camera_y;
+        let alpha = screen.canvas.alpha;
+        let stencil_mode = screen.canvas.stencil_mode;
+        screen.clip0();
+        screen.camera0();
+        screen.dither(1.0);
+        screen.canvas.stencil_mode = StencilMode::Off;
+
+        // Zooming out leaves a border that would otherwise show the previous frame
+        if zoom < 1.0 {
+            screen.canvas.cls(0);
+        }
+        screen.canvas.blt_transform(
+            0.0,
+            0.0,
+            &frame.lock().canvas,
+            0.0,
+            0.0,
+            self.width as f64,
+            self.height as f64,
+            None,
+            None,
+            0.0,
+            zoom,
+            false,
+        );
+
+        screen.canvas.clip_rect = clip_rect;
+        screen.canvas.camera_x = camera_x;
+        screen.canvas.camera_y = camera_y;
+        screen.dither(alpha);
+        screen.canvas.stencil_mode = stencil_mode;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_deadzone() {
+        let mut camera = Camera::new();
+        camera.set_deadzone(20.0, 20.0);
+        camera.follow(5.0, 0.0);
+        camera.update(160.0, 120.0);
+        assert_eq!((camera.x, camera.y), (0.0, 0.0));
+
+        camera.follow(30.0, -4.0);
+        camera.update(160.0, 120.0);
+        assert_eq!((camera.x, camera.y), (20.0, 0.0));
+
+        camera.smoothing = 0.5;
+        camera.follow(50.0, 0.0);
+        camera.update(160.0, 120.0);
+        assert_eq!(camera.x, 30.0);
+        assert_eq!(camera.origin(160.0, 120.0), (-50.0, -60.0));
+    }
+
+    #[test]
+    fn test_clamp_and_zoom() {
+        let tilemap = Tilemap::new(40, 30, crate::tilemap::ImageSource::Index(0));
+        let mut camera = Camera::new();
+        camera.clamp_to_tilemap(&tilemap.lock());
+
+        camera.follow(0.0, 1000.0);
+        camera.update(160.0, 120.0);
+        assert_eq!((camera.x, camera.y), (80.0, 180.0));
+
+        camera.zoom = 2.0;
+        camera.follow(0.0, 0.0);
+        camera.update(160.0, 120.0);
+        assert_eq!((camera.x, camera.y), (40.0, 30.0));
+
+        camera.shake(4.0, Some(0.5));
+        camera.update(160.0, 120.0);
+        assert!(camera.shake_x.abs() <= 4.0 && camera.shake_y.abs() <= 4.0);
+
+        camera.disable();
+        assert!(!camera.enabled);
+        assert_eq!((camera.shake_x, camera.shake_y), (0.0, 0.0));
+        camera.update(160.0, 120.0);
+        assert_eq!((camera.x, camera.y), (40.0, 30.0));
+    }
+}
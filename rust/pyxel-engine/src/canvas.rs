@@ -3,12 +3,37 @@ use std::f64::consts::PI;
 use std::mem::swap;
 
 use crate::rect_area::RectArea;
+use crate::settings::{STENCIL_TEST, STENCIL_WRITE};
 use crate::utils::{f64_to_i32, f64_to_u32};
 
+pub(crate) const DITHERING_MATRIX: [[f32; 4]; 4] = [
+    [1.0 / 16.0, 9.0 / 16.0, 3.0 / 16.0, 11.0 / 16.0],
+    [13.0 / 16.0, 5.0 / 16.0, 15.0 / 16.0, 7.0 / 16.0],
+    [3.0 / 16.0, 11.0 / 16.0, 1.0 / 16.0, 9.0 / 16.0],
+    [15.0 / 16.0, 7.0 / 16.0, 13.0 / 16.0, 5.0 / 16.0],
+];
+
 pub trait ToIndex {
     fn to_index(&self) -> usize;
 }
 
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum StencilMode {
+    Off,
+    Test,
+    Write,
+}
+
+impl StencilMode {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            STENCIL_TEST => Self::Test,
+            STENCIL_WRITE => Self::Write,
+            _ => Self::Off,
+        }
+    }
+}
+
 pub struct Canvas<T: Copy + PartialEq + Default + ToIndex> {
     pub self_rect: RectArea,
     pub clip_rect: RectArea,
@@ -16,6 +41,8 @@ pub struct Canvas<T: Copy + PartialEq + Default + ToIndex> {
     pub camera_y: i32,
     pub alpha: f32,
     pub data: Vec<T>,
+    pub stencil: Vec<bool>,
+    pub stencil_mode: StencilMode,
     should_write: fn(&Canvas<T>, i32, i32) -> bool,
 }
 
@@ -28,6 +55,8 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
             camera_y: 0,
             alpha: 1.0,
             data: vec![T::default(); (width * height) as usize],
+            stencil: Vec::new(),
+            stencil_mode: StencilMode::Off,
             should_write: Self::should_write_always,
         }
     }
@@ -79,6 +108,40 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
+    pub fn set_stencil_mode(&mut self, mode: StencilMode) {
+        if mode != StencilMode::Off {
+            self.ensure_stencil();
+        }
+        self.stencil_mode = mode;
+    }
+
+    pub fn stencil_from(&mut self, canvas: &Self, transparent: Option<T>) {
+        let transparent = transparent.unwrap_or_default();
+        self.ensure_stencil();
+        self.stencil.fill(false);
+
+        let stride = self.width() as usize;
+        let width = self.width().min(canvas.width());
+        let height = self.height().min(canvas.height());
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                self.stencil[stride * y + x] = canvas.read_data(x, y) != transparent;
+            }
+        }
+    }
+
+    pub fn stencil_fill(&mut self, value: bool) {
+        self.ensure_stencil();
+        self.stencil.fill(value);
+    }
+
+    pub fn stencil_invert(&mut self) {
+        self.ensure_stencil();
+        for bit in &mut self.stencil {
+            *bit = !*bit;
+        }
+    }
+
     pub fn cls(&mut self, value: T) {
         let width = self.width();
         let height = self.height();
@@ -368,28 +431,32 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
             return;
         }
 
+        // Dithering and stencil can leave pixels unchanged, so track visits separately
+        let width = self.width() as usize;
+        let mut visited = vec![false; self.data.len()];
+        let is_target = |canvas: &Self, visited: &[bool], x: i32, y: i32| {
+            !visited[width * y as usize + x as usize]
+                && canvas.read_data(x as usize, y as usize) == dst_value
+        };
+
         let mut visit_stack = Vec::new();
         visit_stack.push((x, y));
         while let Some((x, y)) = visit_stack.pop() {
-            if !self.clip_rect.contains(x, y) || self.read_data(x as usize, y as usize) != dst_value
-            {
+            if !self.clip_rect.contains(x, y) || !is_target(self, &visited, x, y) {
                 continue;
             }
 
             let mut left = x;
             let mut right = x;
-            while left > self.clip_rect.left()
-                && self.read_data((left - 1) as usize, y as usize) == dst_value
-            {
+            while left > self.clip_rect.left() && is_target(self, &visited, left - 1, y) {
                 left -= 1;
             }
-            while right < self.clip_rect.right()
-                && self.read_data((right + 1) as usize, y as usize) == dst_value
-            {
+            while right < self.clip_rect.right() && is_target(self, &visited, right + 1, y) {
                 right += 1;
             }
 
             for xi in left..=right {
+                visited[width * y as usize + xi as usize] = true;
                 self.write_data(xi as usize, y as usize, value);
             }
 
@@ -398,8 +465,7 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
                     let mut scan_x = left;
                     let mut in_segment = false;
                     while scan_x <= right {
-                        let is_target =
-                            self.read_data(scan_x as usize, scan_y as usize) == dst_value;
+                        let is_target = is_target(self, &visited, scan_x, scan_y);
                         if is_target && !in_segment {
                             visit_stack.push((scan_x, scan_y));
                             in_segment = true;
@@ -413,6 +479,73 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
+    pub fn linear_gradient(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        angle: f64,
+        ramp: &[T],
+    ) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        let width = f64_to_u32(width);
+        let height = f64_to_u32(height);
+        let rect = RectArea::new(x, y, width, height).intersects(self.clip_rect);
+        if rect.is_empty() || ramp.is_empty() {
+            return;
+        }
+
+        let angle = angle * PI / 180.0;
+        let dir_x = f64::cos(angle);
+        let dir_y = f64::sin(angle);
+        let half_width = (width as f64 - 1.0) / 2.0;
+        let half_height = (height as f64 - 1.0) / 2.0;
+        let cx = x as f64 + half_width;
+        let cy = y as f64 + half_height;
+        let extent = half_width * dir_x.abs() + half_height * dir_y.abs();
+
+        for yi in rect.top()..=rect.bottom() {
+            for xi in rect.left()..=rect.right() {
+                let t = if extent > 0.0 {
+                    ((xi as f64 - cx) * dir_x + (yi as f64 - cy) * dir_y) / (extent * 2.0) + 0.5
+                } else {
+                    0.0
+                };
+                let value = Self::ramp_value(ramp, t, xi, yi);
+                self.write_data(xi as usize, yi as usize, value);
+            }
+        }
+    }
+
+    pub fn radial_gradient(&mut self, x: f64, y: f64, radius: f64, ramp: &[T]) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        let radius = f64_to_u32(radius) as i32;
+        if ramp.is_empty() {
+            return;
+        }
+
+        for yi in (y - radius)..=(y + radius) {
+            for xi in (x - radius)..=(x + radius) {
+                let dx = (xi - x) as f64;
+                let dy = (yi - y) as f64;
+                let dist = (dx * dx + dy * dy).sqrt();
+                if dist > radius as f64 + 0.5 {
+                    continue;
+                }
+                let t = if radius > 0 {
+                    dist / radius as f64
+                } else {
+                    0.0
+                };
+                let value = Self::ramp_value(ramp, t, xi, yi);
+                self.write_data_with_clipping(xi, yi, value);
+            }
+        }
+    }
+
     pub fn blt(
         &mut self,
         x: f64,
@@ -473,6 +606,115 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
+    pub fn blt_tiled(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        canvas: &Self,
+        canvas_x: f64,
+        canvas_y: f64,
+        tile_width: f64,
+        tile_height: f64,
+        transparent: Option<T>,
+        palette: Option<&[T]>,
+    ) {
+        let x = f64_to_i32(x);
+        let y = f64_to_i32(y);
+        let width = f64_to_i32(width);
+        let height = f64_to_i32(height);
+        let tile_width = f64_to_i32(tile_width).abs();
+        let tile_height = f64_to_i32(tile_height).abs();
+        if tile_width == 0 || tile_height == 0 {
+            return;
+        }
+
+        for yi in (0..height).step_by(tile_height as usize) {
+            for xi in (0..width).step_by(tile_width as usize) {
+                self.blt(
+                    (x + xi) as f64,
+                    (y + yi) as f64,
+                    canvas,
+                    canvas_x,
+                    canvas_y,
+                    tile_width.min(width - xi) as f64,
+                    tile_height.min(height - yi) as f64,
+                    transparent,
+                    palette,
+                );
+            }
+        }
+    }
+
+    pub fn blt_stretch(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        canvas: &Self,
+        canvas_x: f64,
+        canvas_y: f64,
+        canvas_width: f64,
+        canvas_height: f64,
+        transparent: Option<T>,
+        palette: Option<&[T]>,
+        shear: f64,
+    ) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        let width = f64_to_u32(width);
+        let height = f64_to_u32(height);
+        let canvas_x = f64_to_i32(canvas_x);
+        let canvas_y = f64_to_i32(canvas_y);
+        let canvas_width = f64_to_i32(canvas_width);
+        let canvas_height = f64_to_i32(canvas_height);
+        let shear_extent = (shear.abs() * height as f64 / 2.0).ceil() as i32;
+        let rect = RectArea::new(x - shear_extent, y, width + shear_extent as u32 * 2, height)
+            .intersects(self.clip_rect);
+        if rect.is_empty() || width == 0 || canvas_width == 0 || canvas_height == 0 {
+            return;
+        }
+
+        let scale_x = canvas_width.abs() as f64 / width as f64;
+        let scale_y = canvas_height.abs() as f64 / height as f64;
+        let center_y = (height as f64 - 1.0) / 2.0;
+        for yi in rect.top()..=rect.bottom() {
+            let local_y = yi - y;
+            let mut value_y = ((local_y as f64 + 0.5) * scale_y) as i32;
+            if canvas_height < 0 {
+                value_y = canvas_height.abs() - 1 - value_y;
+            }
+            let value_y = canvas_y + value_y;
+            let offset_x = shear * (local_y as f64 - center_y);
+
+            for xi in rect.left()..=rect.right() {
+                let local_x = ((xi - x) as f64 - offset_x).floor();
+                if local_x < 0.0 || local_x >= width as f64 {
+                    continue;
+                }
+                let mut value_x = ((local_x + 0.5) * scale_x) as i32;
+                if canvas_width < 0 {
+                    value_x = canvas_width.abs() - 1 - value_x;
+                }
+                let value_x = canvas_x + value_x;
+
+                if !canvas.self_rect.contains(value_x, value_y) {
+                    continue;
+                }
+                let value = canvas.read_data(value_x as usize, value_y as usize);
+                if let Some(transparent) = transparent {
+                    if value == transparent {
+                        continue;
+                    }
+                }
+                let value = palette.map_or(value, |palette| palette[value.to_index()]);
+                self.write_data(xi as usize, yi as usize, value);
+            }
+        }
+    }
+
     pub fn blt_transform(
         &mut self,
         x: f64,
@@ -556,15 +798,28 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
 
     pub fn write_data(&mut self, x: usize, y: usize, value: T) {
         if (self.should_write)(self, x as i32, y as i32) {
-            let width = self.width() as usize;
-            self.data[width * y + x] = value;
+            let index = self.width() as usize * y + x;
+            match self.stencil_mode {
+                StencilMode::Off => self.data[index] = value,
+                StencilMode::Test => {
+                    if self.stencil[index] {
+                        self.data[index] = value;
+                    }
+                }
+                StencilMode::Write => self.stencil[index] = value != T::default(),
+            }
         }
     }
 
     fn write_data_with_clipping(&mut self, x: i32, y: i32, value: T) {
-        if (self.should_write)(self, x, y) && self.clip_rect.contains(x, y) {
-            let width = self.width() as usize;
-            self.data[width * y as usize + x as usize] = value;
+        if self.clip_rect.contains(x, y) {
+            self.write_data(x as usize, y as usize, value);
+        }
+    }
+
+    fn ensure_stencil(&mut self) {
+        if self.stencil.len() != self.data.len() {
+            self.stencil = vec![false; self.data.len()];
         }
     }
 
@@ -601,14 +856,21 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
     }
 
     fn should_write_normal(&self, x: i32, y: i32) -> bool {
-        const DITHERING_MATRIX: [[f32; 4]; 4] = [
-            [1.0 / 16.0, 9.0 / 16.0, 3.0 / 16.0, 11.0 / 16.0],
-            [13.0 / 16.0, 5.0 / 16.0, 15.0 / 16.0, 7.0 / 16.0],
-            [3.0 / 16.0, 11.0 / 16.0, 1.0 / 16.0, 9.0 / 16.0],
-            [15.0 / 16.0, 7.0 / 16.0, 13.0 / 16.0, 5.0 / 16.0],
-        ];
+        self.alpha > Self::dithering_threshold(x, y)
+    }
 
-        self.alpha > DITHERING_MATRIX[y.rem_euclid(4) as usize][x.rem_euclid(4) as usize]
+    fn dithering_threshold(x: i32, y: i32) -> f32 {
+        DITHERING_MATRIX[y.rem_euclid(4) as usize][x.rem_euclid(4) as usize]
+    }
+
+    fn ramp_value(ramp: &[T], t: f64, x: i32, y: i32) -> T {
+        let pos = t.clamp(0.0, 1.0) * (ramp.len() - 1) as f64;
+        let index = pos as usize;
+        if index + 1 < ramp.len() && (pos - index as f64) as f32 > Self::dithering_threshold(x, y) {
+            ramp[index + 1]
+        } else {
+            ramp[index]
+        }
     }
 }
 
@@ -680,7 +942,7 @@ impl CopyArea {
 
 #[cfg(test)]
 mod tests {
-    use super::Canvas;
+    use super::{Canvas, StencilMode};
 
     #[test]
     fn fill_doesnt_overflow_stack() {
@@ -689,4 +951,74 @@ mod tests {
         // this assertion won't even be reached if the above line overflows the stack
         assert_eq!(canvas.read_data(128, 128), 8);
     }
+
+    #[test]
+    fn stencil_test_gates_writes() {
+        let mut canvas = Canvas::<u8>::new(16, 16);
+        canvas.set_stencil_mode(StencilMode::Write);
+        canvas.rect(4.0, 4.0, 4.0, 4.0, 1);
+        canvas.set_stencil_mode(StencilMode::Test);
+        canvas.rect(0.0, 0.0, 16.0, 16.0, 7);
+        assert_eq!(canvas.read_data(5, 5), 7);
+        assert_eq!(canvas.read_data(0, 0), 0);
+
+        canvas.stencil_invert();
+        canvas.rect(0.0, 0.0, 16.0, 16.0, 3);
+        assert_eq!(canvas.read_data(5, 5), 7);
+        assert_eq!(canvas.read_data(0, 0), 3);
+    }
+
+    #[test]
+    fn fill_terminates_with_stencil() {
+        let mut canvas = Canvas::<u8>::new(16, 16);
+        canvas.set_stencil_mode(StencilMode::Test);
+        canvas.fill(0.0, 0.0, 8);
+        assert_eq!(canvas.read_data(8, 8), 0);
+    }
+
+    #[test]
+    fn linear_gradient_spans_ramp() {
+        let mut canvas = Canvas::<u8>::new(16, 4);
+        canvas.linear_gradient(0.0, 0.0, 16.0, 4.0, 0.0, &[1, 2, 3]);
+        assert_eq!(canvas.read_data(0, 0), 1);
+        assert_eq!(canvas.read_data(15, 3), 3);
+        assert!((1..=3).contains(&canvas.read_data(8, 2)));
+    }
+
+    #[test]
+    fn blt_tiled_repeats_source() {
+        let mut src = Canvas::<u8>::new(2, 1);
+        src.pset(0.0, 0.0, 1);
+        src.pset(1.0, 0.0, 2);
+        let mut canvas = Canvas::<u8>::new(8, 2);
+        canvas.blt_tiled(0.0, 0.0, 5.0, 2.0, &src, 0.0, 0.0, 2.0, 1.0, None, None);
+        assert_eq!(canvas.data[..8], [1, 2, 1, 2, 1, 0, 0, 0]);
+        assert_eq!(canvas.read_data(2, 1), 1);
+    }
+
+    #[test]
+    fn blt_stretch_scales_each_axis() {
+        let mut src = Canvas::<u8>::new(2, 2);
+        src.pset(1.0, 1.0, 5);
+        let mut canvas = Canvas::<u8>::new(8, 4);
+        canvas.blt_stretch(
+            0.0, 0.0, 8.0, 4.0, &src, 0.0, 0.0, 2.0, 2.0, None, None, 0.0,
+        );
+        assert_eq!(canvas.read_data(3, 1), 0);
+        assert_eq!(canvas.read_data(4, 2), 5);
+        assert_eq!(canvas.read_data(7, 3), 5);
+    }
+
+    #[test]
+    fn blt_stretch_shears_rows() {
+        let mut src = Canvas::<u8>::new(1, 1);
+        src.pset(0.0, 0.0, 3);
+        let mut canvas = Canvas::<u8>::new(8, 3);
+        canvas.blt_stretch(
+            2.0, 0.0, 2.0, 3.0, &src, 0.0, 0.0, 1.0, 1.0, None, None, 1.0,
+        );
+        assert_eq!(canvas.data[..8], [0, 3, 3, 0, 0, 0, 0, 0]);
+        assert_eq!(canvas.data[8..16], [0, 0, 3, 3, 0, 0, 0, 0]);
+        assert_eq!(canvas.data[16..], [0, 0, 0, 3, 3, 0, 0, 0]);
+    }
 }
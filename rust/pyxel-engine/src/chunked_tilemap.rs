@@ -0,0 +1,541 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+
+use crate::canvas::{Canvas, ToIndex};
+use crate::image::{Color, Image};
+use crate::pyxel::Pyxel;
+use crate::rect_area::RectArea;
+use crate::settings::{TILEMAP_CHUNK_SIZE, TILE_SIZE};
+use crate::tile_animation::TileAnimation;
+use crate::tilemap::{ImageSource, ImageTileCoord, SharedTilemap, Tile, Tilemap};
+use crate::utils::{f64_to_i32, f64_to_u32, parse_hex_string, simplify_string};
+
+const CHUNK_SIZE: i32 = TILEMAP_CHUNK_SIZE as i32;
+
+pub type ChunkCoord = (i32, i32);
+
+#[derive(Copy, Clone, PartialEq, Default)]
+struct Mark(bool);
+
+impl ToIndex for Mark {
+    fn to_index(&self) -> usize {
+        0
+    }
+}
+
+pub struct ChunkedTilemap {
+    width: u32,
+    height: u32,
+    chunks: HashMap<ChunkCoord, Canvas<Tile>>,
+    pub imgsrc: ImageSource,
+    pub default_tile: Tile,
+    pub animations: HashMap<Tile, TileAnimation>,
+    clip_rect: RectArea,
+    camera_x: i32,
+    camera_y: i32,
+}
+
+pub type SharedChunkedTilemap = shared_type!(ChunkedTilemap);
+
+impl ChunkedTilemap {
+    pub fn new(width: u32, height: u32, imgsrc: ImageSource) -> SharedChunkedTilemap {
+        new_shared_type!(Self {
+            width,
+            height,
+            chunks: HashMap::new(),
+            imgsrc,
+            default_tile: (0, 0),
+            animations: HashMap::new(),
+            clip_rect: RectArea::new(0, 0, width, height),
+            camera_x: 0,
+            camera_y: 0,
+        })
+    }
+
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn chunk_coords(&self) -> Vec<ChunkCoord> {
+        let mut coords: Vec<ChunkCoord> = self.chunks.keys().copied().collect();
+        coords.sort_unstable();
+        coords
+    }
+
+    pub fn is_chunk_loaded(&self, chunk_x: i32, chunk_y: i32) -> bool {
+        self.chunks.contains_key(&(chunk_x, chunk_y))
+    }
+
+    pub fn unload_chunk(&mut self, chunk_x: i32, chunk_y: i32) {
+        self.chunks.remove(&(chunk_x, chunk_y));
+    }
+
+    pub fn load_chunk(&mut self, chunk_x: i32, chunk_y: i32, filename: &str) -> Result<(), String> {
+        let text = fs::read_to_string(filename)
+            .map_err(|_| format!("Failed to open file '{filename}'"))?;
+        let lines: Vec<String> = text.lines().map(simplify_string).collect();
+        if lines.len() != CHUNK_SIZE as usize
+            || lines
+                .iter()
+                .any(|line| line.len() != CHUNK_SIZE as usize * 4)
+        {
+            return Err(format!("Invalid chunk size in '{filename}'"));
+        }
+
+        let mut chunk = Canvas::new(TILEMAP_CHUNK_SIZE, TILEMAP_CHUNK_SIZE);
+        for (y, line) in lines.iter().enumerate() {
+            for x in 0..CHUNK_SIZE as usize {
+                let tile = parse_hex_string(&line[x * 4..x * 4 + 4])
+                    .map_err(|_| format!("Invalid tile data in '{filename}'"))?;
+                chunk.write_data(
+                    x,
+                    y,
+                    (
+                        ((tile >> 8) & 0xff) as ImageTileCoord,
+                        (tile & 0xff) as ImageTileCoord,
+                    ),
+                );
+            }
+        }
+        self.chunks.insert((chunk_x, chunk_y), chunk);
+        Ok(())
+    }
+
+    pub fn save_chunk(&self, chunk_x: i32, chunk_y: i32, filename: &str) -> Result<(), String> {
+        let mut text = String::new();
+        for y in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let tile = self.tile(chunk_x * CHUNK_SIZE + x, chunk_y * CHUNK_SIZE + y);
+                let _ = write!(text, "{:02x}{:02x}", tile.0, tile.1);
+            }
+            text.push('\n');
+        }
+        fs::write(filename, text).map_err(|_| format!("Failed to open file '{filename}'"))
+    }
+
+    pub fn tile(&self, x: i32, y: i32) -> Tile {
+        let (chunk_coord, local_x, local_y) = Self::split_coord(x, y);
+        self.chunks
+            .get(&chunk_coord)
+            .map_or(self.default_tile, |chunk| chunk.read_data(local_x, local_y))
+    }
+
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: Tile) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let (chunk_coord, local_x, local_y) = Self::split_coord(x, y);
+        let default_tile = self.default_tile;
+        self.chunks
+            .entry(chunk_coord)
+            .or_insert_with(|| {
+                let mut chunk = Canvas::new(TILEMAP_CHUNK_SIZE, TILEMAP_CHUNK_SIZE);
+                chunk.cls(default_tile);
+                chunk
+            })
+            .write_data(local_x, local_y, tile);
+    }
+
+    pub fn region(&self, x: i32, y: i32, width: u32, height: u32) -> SharedTilemap {
+        let tilemap = Tilemap::new(width, height, self.imgsrc.clone());
+        {
+            let mut tilemap = tilemap.lock();
+            tilemap.animations.clone_from(&self.animations);
+            for yi in 0..height as i32 {
+                for xi in 0..width as i32 {
+                    let tile = self.tile(x + xi, y + yi);
+                    tilemap.canvas.write_data(xi as usize, yi as usize, tile);
+                }
+            }
+        }
+        tilemap
+    }
+
+    pub fn clip(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        let rect = RectArea::new(
+            f64_to_i32(x),
+            f64_to_i32(y),
+            f64_to_u32(width),
+            f64_to_u32(height),
+        );
+        self.clip_rect = RectArea::new(0, 0, self.width, self.height).intersects(rect);
+    }
+
+    pub fn clip0(&mut self) {
+        self.clip_rect = RectArea::new(0, 0, self.width, self.height);
+    }
+
+    pub fn camera(&mut self, x: f64, y: f64) {
+        self.camera_x = f64_to_i32(x);
+        self.camera_y = f64_to_i32(y);
+    }
+
+    pub fn camera0(&mut self) {
+        self.camera_x = 0;
+        self.camera_y = 0;
+    }
+
+    pub fn cls(&mut self, tile: Tile) {
+        // Untouched chunks read as the default tile, so nothing is allocated
+        self.chunks.clear();
+        self.default_tile = tile;
+    }
+
+    pub fn pget(&self, x: f64, y: f64) -> Tile {
+        let (x, y) = (f64_to_i32(x), f64_to_i32(y));
+        if self.clip_rect.contains(x, y) {
+            self.tile(x, y)
+        } else {
+            (0, 0)
+        }
+    }
+
+    pub fn pset(&mut self, x: f64, y: f64, tile: Tile) {
+        self.draw_shape(&[(x, y)], tile, |canvas| {
+            canvas.pset(x, y, Mark(true));
+        });
+    }
+
+    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, tile: Tile) {
+        self.draw_shape(&[(x1, y1), (x2, y2)], tile, |canvas| {
+            canvas.line(x1, y1, x2, y2, Mark(true));
+        });
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, tile: Tile) {
+        self.draw_shape(&[(x, y), (x + width, y + height)], tile, |canvas| {
+            canvas.rect(x, y, width, height, Mark(true));
+        });
+    }
+
+    pub fn rectb(&mut self, x: f64, y: f64, width: f64, height: f64, tile: Tile) {
+        self.draw_shape(&[(x, y), (x + width, y + height)], tile, |canvas| {
+            canvas.rectb(x, y, width, height, Mark(true));
+        });
+    }
+
+    pub fn circ(&mut self, x: f64, y: f64, radius: f64, tile: Tile) {
+        let bounds = [(x - radius, y - radius), (x + radius, y + radius)];
+        self.draw_shape(&bounds, tile, |canvas| {
+            canvas.circ(x, y, radius, Mark(true));
+        });
+    }
+
+    pub fn circb(&mut self, x: f64, y: f64, radius: f64, tile: Tile) {
+        let bounds = [(x - radius, y - radius), (x + radius, y + radius)];
+        self.draw_shape(&bounds, tile, |canvas| {
+            canvas.circb(x, y, radius, Mark(true));
+        });
+    }
+
+    pub fn elli(&mut self, x: f64, y: f64, width: f64, height: f64, tile: Tile) {
+        self.draw_shape(&[(x, y), (x + width, y + height)], tile, |canvas| {
+            canvas.elli(x, y, width, height, Mark(true));
+        });
+    }
+
+    pub fn ellib(&mut self, x: f64, y: f64, width: f64, height: f64, tile: Tile) {
+        self.draw_shape(&[(x, y), (x + width, y + height)], tile, |canvas| {
+            canvas.ellib(x, y, width, height, Mark(true));
+        });
+    }
+
+    pub fn tri(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: Tile) {
+        self.draw_shape(&[(x1, y1), (x2, y2), (x3, y3)], tile, |canvas| {
+            canvas.tri(x1, y1, x2, y2, x3, y3, Mark(true));
+        });
+    }
+
+    pub fn trib(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: Tile) {
+        self.draw_shape(&[(x1, y1), (x2, y2), (x3, y3)], tile, |canvas| {
+            canvas.trib(x1, y1, x2, y2, x3, y3, Mark(true));
+        });
+    }
+
+    pub fn fill(&mut self, x: f64, y: f64, tile: Tile) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        if !self.clip_rect.contains(x, y) {
+            return;
+        }
+        let dst_tile = self.tile(x, y);
+        if tile == dst_tile {
+            return;
+        }
+
+        // Filled tiles no longer match, so they double as the visited set
+        let mut visit_stack = vec![(x, y)];
+        while let Some((x, y)) = visit_stack.pop() {
+            if !self.clip_rect.contains(x, y) || self.tile(x, y) != dst_tile {
+                continue;
+            }
+            self.set_tile(x, y, tile);
+            visit_stack.extend([(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]);
+        }
+    }
+
+    pub fn blt(
+        &mut self,
+        x: f64,
+        y: f64,
+        tilemap: SharedTilemap,
+        tilemap_x: f64,
+        tilemap_y: f64,
+        width: f64,
+        height: f64,
+        transparent: Option<Tile>,
+    ) {
+        let x = f64_to_i32(x) - self.camera_x;
+        let y = f64_to_i32(y) - self.camera_y;
+        let tilemap_x = f64_to_i32(tilemap_x);
+        let tilemap_y = f64_to_i32(tilemap_y);
+        let width = f64_to_i32(width);
+        let height = f64_to_i32(height);
+
+        let tilemap = tilemap.lock();
+        for yi in 0..height.abs() {
+            for xi in 0..width.abs() {
+                let src_x = tilemap_x + if width < 0 { -width - 1 - xi } else { xi };
+                let src_y = tilemap_y + if height < 0 { -height - 1 - yi } else { yi };
+                if !tilemap.canvas.self_rect.contains(src_x, src_y)
+                    || !self.clip_rect.contains(x + xi, y + yi)
+                {
+                    continue;
+                }
+                let tile = tilemap.canvas.read_data(src_x as usize, src_y as usize);
+                if transparent == Some(tile) {
+                    continue;
+                }
+                self.set_tile(x + xi, y + yi, tile);
+            }
+        }
+    }
+
+    fn draw_shape(
+        &mut self,
+        points: &[(f64, f64)],
+        tile: Tile,
+        draw: impl FnOnce(&mut Canvas<Mark>),
+    ) {
+        // Shapes are rasterized into a scratch mask covering only their bounds
+        let (mut left, mut top) = (i32::MAX, i32::MAX);
+        let (mut right, mut bottom) = (i32::MIN, i32::MIN);
+        for &(x, y) in points {
+            let (x, y) = (f64_to_i32(x) - self.camera_x, f64_to_i32(y) - self.camera_y);
+            left = left.min(x - 1);
+            top = top.min(y - 1);
+            right = right.max(x + 1);
+            bottom = bottom.max(y + 1);
+        }
+        let bounds = RectArea::new(
+            left,
+            top,
+            (right - left + 1) as u32,
+            (bottom - top + 1) as u32,
+        )
+        .intersects(self.clip_rect);
+        if bounds.is_empty() {
+            return;
+        }
+
+        let mut canvas = Canvas::new(bounds.width(), bounds.height());
+        canvas.camera(
+            (bounds.left() + self.camera_x) as f64,
+            (bounds.top() + self.camera_y) as f64,
+        );
+        draw(&mut canvas);
+
+        for y in 0..bounds.height() as usize {
+            for x in 0..bounds.width() as usize {
+                if canvas.read_data(x, y).0 {
+                    self.set_tile(bounds.left() + x as i32, bounds.top() + y as i32, tile);
+                }
+            }
+        }
+    }
+
+    fn split_coord(x: i32, y: i32) -> (ChunkCoord, usize, usize) {
+        (
+            (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE)),
+            x.rem_euclid(CHUNK_SIZE) as usize,
+            y.rem_euclid(CHUNK_SIZE) as usize,
+        )
+    }
+}
+
+impl Image {
+    pub fn bltm_chunked(
+        &mut self,
+        x: f64,
+        y: f64,
+        tilemap: SharedChunkedTilemap,
+        tilemap_x: f64,
+        tilemap_y: f64,
+        width: f64,
+        height: f64,
+        transparent: Option<Color>,
+        rotate: Option<f64>,
+        scale: Option<f64>,
+        time: Option<u32>,
+    ) {
+        // Only the visible tiles are copied into a regular tilemap for drawing
+        let tile_size = TILE_SIZE as i32;
+        let (left, top) = (f64_to_i32(tilemap_x), f64_to_i32(tilemap_y));
+        let right = left + f64_to_i32(width.abs());
+        let bottom = top + f64_to_i32(height.abs());
+
+        let tilemap = tilemap.lock();
+        let tile_left = left.div_euclid(tile_size).max(0);
+        let tile_top = top.div_euclid(tile_size).max(0);
+        let tile_right = (right + tile_size - 1)
+            .div_euclid(tile_size)
+            .min(tilemap.width() as i32);
+        let tile_bottom = (bottom + tile_size - 1)
+            .div_euclid(tile_size)
+            .min(tilemap.height() as i32);
+        if tile_right <= tile_left || tile_bottom <= tile_top {
+            return;
+        }
+
+        let region = tilemap.region(
+            tile_left,
+            tile_top,
+            (tile_right - tile_left) as u32,
+            (tile_bottom - tile_top) as u32,
+        );
+        drop(tilemap);
+        self.bltm(
+            x,
+            y,
+            region,
+            tilemap_x - (tile_left * tile_size) as f64,
+            tilemap_y - (tile_top * tile_size) as f64,
+            width,
+            height,
+            transparent,
+            rotate,
+            scale,
+            time,
+        );
+    }
+}
+
+impl Pyxel {
+    pub fn bltm_chunked(
+        &self,
+        x: f64,
+        y: f64,
+        tilemap: SharedChunkedTilemap,
+        tilemap_x: f64,
+        tilemap_y: f64,
+        width: f64,
+        height: f64,
+        color_key: Option<Color>,
+        rotate: Option<f64>,
+        scale: Option<f64>,
+    ) {
+        self.screen.lock().bltm_chunked(
+            x,
+            y,
+            tilemap,
+            tilemap_x,
+            tilemap_y,
+            width,
+            height,
+            color_key,
+            rotate,
+            scale,
+            Some(self.frame_count),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_drawing() {
+        let tilemap = ChunkedTilemap::new(4096, 4096, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+
+        tilemap.pset(3000.0, 3000.0, (1, 2));
+        assert_eq!(tilemap.chunk_coords(), vec![(46, 46)]);
+        assert_eq!(tilemap.pget(3000.0, 3000.0), (1, 2));
+        assert_eq!(tilemap.pget(10.0, 10.0), (0, 0));
+
+        tilemap.rect(60.0, 0.0, 8.0, 2.0, (3, 3));
+        assert_eq!(tilemap.chunk_coords(), vec![(0, 0), (1, 0), (46, 46)]);
+        assert_eq!(tilemap.pget(67.0, 1.0), (3, 3));
+        assert_eq!(tilemap.pget(68.0, 1.0), (0, 0));
+
+        tilemap.camera(10.0, 0.0);
+        tilemap.line(10.0, 5.0, 13.0, 5.0, (4, 4));
+        assert_eq!(tilemap.pget(0.0, 5.0), (4, 4));
+        assert_eq!(tilemap.pget(3.0, 5.0), (4, 4));
+        assert_eq!(tilemap.pget(4.0, 5.0), (0, 0));
+        tilemap.camera0();
+
+        tilemap.cls((5, 5));
+        assert!(tilemap.chunk_coords().is_empty());
+        assert_eq!(tilemap.pget(100.0, 100.0), (5, 5));
+    }
+
+    #[test]
+    fn test_save_load_chunk() {
+        let filename = std::env::temp_dir().join("pyxel_chunk_test.txt");
+        let filename = filename.to_str().unwrap();
+
+        let tilemap = ChunkedTilemap::new(256, 256, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.pset(70.0, 5.0, (7, 8));
+        tilemap.save_chunk(1, 0, filename).unwrap();
+        tilemap.unload_chunk(1, 0);
+        assert_eq!(tilemap.pget(70.0, 5.0), (0, 0));
+
+        tilemap.load_chunk(1, 0, filename).unwrap();
+        fs::remove_file(filename).unwrap();
+        assert!(tilemap.is_chunk_loaded(1, 0));
+        assert_eq!(tilemap.pget(70.0, 5.0), (7, 8));
+
+        let region = tilemap.region(68, 4, 4, 2);
+        assert_eq!(region.lock().pget(2.0, 1.0), (7, 8));
+    }
+
+    #[test]
+    fn test_bltm_chunked() {
+        let source = Image::new(16, 8);
+        source.lock().rect(8.0, 0.0, 8.0, 8.0, 2);
+        let tilemap = ChunkedTilemap::new(256, 1, ImageSource::Image(source));
+        tilemap.lock().pset(64.0, 0.0, (1, 0));
+
+        // Tiles 63 and 64 lie on both sides of a chunk boundary
+        let image = Image::new(16, 8);
+        let mut image = image.lock();
+        let u = (63 * TILE_SIZE) as f64;
+        image.bltm_chunked(
+            0.0,
+            0.0,
+            tilemap.clone(),
+            u,
+            0.0,
+            16.0,
+            8.0,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!((image.pget(7.0, 0.0), image.pget(8.0, 7.0)), (0, 2));
+
+        image.bltm_chunked(
+            0.0, 0.0, tilemap, u, 0.0, -16.0, 8.0, None, None, None, None,
+        );
+        assert_eq!((image.pget(7.0, 0.0), image.pget(8.0, 7.0)), (2, 0));
+    }
+}
@@ -1,11 +1,20 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use ab_glyph::{point, Font as _, FontVec, PxScale, ScaleFont};
 
 use crate::canvas::Canvas;
 use crate::image::Color;
+use crate::pyxel::{FONT_GLYPHS, FONT_IMAGE};
+use crate::settings::{
+    DEFAULT_FONT_SIZE, FONT_HEIGHT, FONT_WIDTH, MAX_FONT_GLYPHS, MAX_GLYPH_WIDTH, NUM_FONT_ROWS,
+};
+use crate::utils;
+
+const BUILTIN_FONT_ASCENT: i32 = FONT_HEIGHT as i32 - 1;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default)]
 struct BoundingBox {
     width: i32,
     height: i32,
@@ -22,37 +31,53 @@ pub struct Glyph {
 pub struct Font {
     font_bounding_box: BoundingBox,
     glyphs: HashMap<i32, Glyph>,
+    kerning: HashMap<(i32, i32), i32>,
+    outline_font: Option<(FontVec, PxScale)>,
+    fallbacks: Vec<SharedFont>,
 }
 
 pub type SharedFont = shared_type!(Font);
 
 impl Font {
-    pub fn new(filename: &str) -> SharedFont {
-        let mut font_bounding_box = BoundingBox {
-            width: 0,
-            height: 0,
-            x: 0,
-            y: 0,
-        };
+    pub fn new(filename: &str, font_size: Option<f64>) -> Result<SharedFont, String> {
+        let data = fs::read(filename).map_err(|_| format!("Failed to open file '{filename}'"))?;
+        let is_outline_font = Path::new(filename)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("ttf") || ext.eq_ignore_ascii_case("otf"));
+        let font = if is_outline_font {
+            Self::from_ttf(data, font_size.unwrap_or(DEFAULT_FONT_SIZE))
+        } else {
+            Self::from_bdf(&String::from_utf8_lossy(&data))
+        }
+        .map_err(|message| format!("{message} in '{filename}'"))?;
+
+        Ok(new_shared_type!(font))
+    }
+
+    fn from_bdf(bdf_text: &str) -> Result<Self, String> {
+        fn parse_values(line: &str, count: usize) -> Option<Vec<i32>> {
+            let values: Vec<i32> = line
+                .split_whitespace()
+                .skip(1)
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .ok()?;
+            (values.len() >= count).then_some(values)
+        }
+
+        let mut font_bounding_box = BoundingBox::default();
         let mut glyphs = HashMap::new();
         let mut code = None;
         let mut bitmap = None;
         let mut dwidth = 0;
-        let mut bbx = BoundingBox {
-            width: 0,
-            height: 0,
-            x: 0,
-            y: 0,
-        };
+        let mut bbx = BoundingBox::default();
+        let mut kerning = HashMap::new();
+
+        for (line_index, line) in bdf_text.lines().enumerate() {
+            let parse_error = || format!("Failed to parse BDF line {}", line_index + 1);
 
-        let file = File::open(filename).unwrap();
-        for line in BufReader::new(file).lines().map_while(Result::ok) {
             if line.starts_with("FONTBOUNDINGBOX") {
-                let values: Vec<i32> = line
-                    .split_whitespace()
-                    .skip(1)
-                    .map(|v| v.parse().unwrap())
-                    .collect();
+                let values = parse_values(line, 4).ok_or_else(parse_error)?;
                 font_bounding_box = BoundingBox {
                     width: values[0],
                     height: values[1],
@@ -60,31 +85,21 @@ impl Font {
                     y: values[3],
                 };
             } else if line.starts_with("ENCODING") {
-                code = Some(
-                    line.split_whitespace()
-                        .nth(1)
-                        .unwrap()
-                        .parse::<i32>()
-                        .unwrap(),
-                );
+                code = Some(parse_values(line, 1).ok_or_else(parse_error)?[0]);
             } else if line.starts_with("DWIDTH") {
-                dwidth = line
-                    .split_whitespace()
-                    .nth(1)
-                    .map(|v| v.parse().unwrap())
-                    .unwrap();
+                dwidth = parse_values(line, 1).ok_or_else(parse_error)?[0];
             } else if line.starts_with("BBX") {
-                let values: Vec<i32> = line
-                    .split_whitespace()
-                    .skip(1)
-                    .map(|v| v.parse().unwrap())
-                    .collect();
+                let values = parse_values(line, 4).ok_or_else(parse_error)?;
                 bbx = BoundingBox {
                     width: values[0],
                     height: values[1],
                     x: values[2],
                     y: values[3],
                 };
+            } else if line.starts_with("KPX") {
+                // BDF has no kerning table, so AFM style 'KPX code1 code2 offset' lines are accepted
+                let values = parse_values(line, 3).ok_or_else(parse_error)?;
+                kerning.insert((values[0], values[1]), values[2]);
             } else if line.starts_with("BITMAP") {
                 bitmap = Some(Vec::new());
             } else if line.starts_with("ENDCHAR") {
@@ -101,21 +116,155 @@ impl Font {
                 bitmap = None;
             } else if let Some(ref mut bitmap) = bitmap {
                 let hex_string = line.trim();
-                let bin_string = u32::from_str_radix(hex_string, 16).unwrap();
+                if hex_string.is_empty() || hex_string.len() > 8 {
+                    return Err(parse_error());
+                }
+                let bin_string = u32::from_str_radix(hex_string, 16).map_err(|_| parse_error())?;
                 bitmap.push(bin_string.reverse_bits() >> (32 - hex_string.len() * 4));
             }
         }
 
-        new_shared_type!(Font {
+        Ok(Self {
+            font_bounding_box,
+            glyphs,
+            kerning,
+            outline_font: None,
+            fallbacks: Vec::new(),
+        })
+    }
+
+    fn from_ttf(ttf_data: Vec<u8>, font_size: f64) -> Result<Self, String> {
+        let font = FontVec::try_from_vec(ttf_data).map_err(|_| "Failed to parse font file")?;
+        let scale = PxScale::from(font_size as f32);
+        let scaled_font = font.as_scaled(scale);
+        let ascent = scaled_font.ascent().round() as i32;
+        let descent = scaled_font.descent().round() as i32;
+        let mut font_bounding_box = BoundingBox {
+            width: 0,
+            height: ascent - descent,
+            x: 0,
+            y: descent,
+        };
+        let mut glyphs = HashMap::new();
+
+        for (glyph_id, c) in font.codepoint_ids() {
+            let dwidth = scaled_font.h_advance(glyph_id).round() as i32;
+            font_bounding_box.width = font_bounding_box.width.max(dwidth);
+
+            let glyph = glyph_id.with_scale_and_position(scale, point(0.0, 0.0));
+            let Some(outline) = font.outline_glyph(glyph) else {
+                glyphs.insert(
+                    c as i32,
+                    Glyph {
+                        dwidth,
+                        bbx: BoundingBox::default(),
+                        bitmap: Vec::new(),
+                    },
+                );
+                continue;
+            };
+
+            // Coverage is thresholded so that glyphs keep hard pixel edges
+            let bounds = outline.px_bounds();
+            let width = (bounds.width() as i32).min(MAX_GLYPH_WIDTH as i32);
+            let height = bounds.height() as i32;
+            let mut bitmap = vec![0; height as usize];
+            outline.draw(|x, y, coverage| {
+                if coverage >= 0.5 && (x as i32) < width {
+                    bitmap[y as usize] |= 1 << x;
+                }
+            });
+
+            glyphs.insert(
+                c as i32,
+                Glyph {
+                    dwidth,
+                    bbx: BoundingBox {
+                        width,
+                        height,
+                        x: bounds.min.x as i32,
+                        y: -(bounds.max.y as i32),
+                    },
+                    bitmap,
+                },
+            );
+        }
+
+        Ok(Self {
             font_bounding_box,
             glyphs,
+            kerning: HashMap::new(),
+            outline_font: Some((font, scale)),
+            fallbacks: Vec::new(),
+        })
+    }
+
+    pub fn set_fallbacks(&mut self, fonts: Vec<SharedFont>) -> Result<(), String> {
+        // Existing chains are acyclic, so a cycle can only lead back to this font
+        let self_ptr: *const Self = self;
+        let mut visited = HashSet::new();
+        let mut pending = fonts.clone();
+        while let Some(font) = pending.pop() {
+            let font_ptr = font.data_ptr().cast_const();
+            if font_ptr == self_ptr {
+                return Err("Font fallback chain contains a cycle".to_string());
+            }
+            if visited.insert(font_ptr) {
+                pending.extend(font.lock().fallbacks.iter().cloned());
+            }
+        }
+
+        self.fallbacks = fonts;
+        Ok(())
+    }
+
+    pub fn set_kerning(&mut self, left: char, right: char, offset: i32) {
+        self.kerning.insert((left as i32, right as i32), offset);
+    }
+
+    pub fn kerning(&self, left: char, right: char) -> i32 {
+        if let Some(offset) = self.kerning.get(&(left as i32, right as i32)) {
+            return *offset;
+        }
+        self.outline_font.as_ref().map_or(0, |(font, scale)| {
+            let scaled_font = font.as_scaled(*scale);
+            scaled_font
+                .kern(font.glyph_id(left), font.glyph_id(right))
+                .round() as i32
         })
     }
 
     pub fn text_width(&self, s: &str) -> i32 {
-        s.chars()
-            .map(|c| self.glyphs.get(&(c as i32)).map_or(0, |glyph| glyph.dwidth))
-            .sum()
+        let mut width = 0;
+        let mut prev = None;
+        for c in s.chars() {
+            if let Some(prev) = prev {
+                width += self.kerning(prev, c);
+            }
+            width += self.char_width(c);
+            prev = Some(c);
+        }
+        width
+    }
+
+    pub fn char_width(&self, c: char) -> i32 {
+        if let Some(glyph) = self.glyphs.get(&(c as i32)) {
+            return glyph.dwidth;
+        }
+        for fallback in &self.fallbacks {
+            if let Some(glyph) = fallback.lock().glyphs.get(&(c as i32)) {
+                return glyph.dwidth;
+            }
+        }
+        if builtin_glyph_pos(c).is_some() {
+            FONT_WIDTH as i32
+        } else {
+            0
+        }
+    }
+
+    pub fn line_height(&self) -> i32 {
+        self.font_bounding_box.height
     }
 
     pub(crate) fn draw(
@@ -126,15 +275,76 @@ impl Font {
         text: &str,
         color: Color,
     ) {
+        let start_x = x;
         let mut x = x;
+        let mut y = y;
+        let mut prev = None;
         for c in text.chars() {
-            if let Some(glyph) = self.glyphs.get(&(c as i32)) {
-                self.draw_glyph(canvas, x, y, glyph, color);
-                x += glyph.dwidth;
+            if c == '\n' {
+                x = start_x;
+                y += self.line_height();
+                prev = None;
+                continue;
             }
+            if let Some(prev) = prev {
+                x += self.kerning(prev, c);
+            }
+            x += self.draw_char(canvas, x, y, c, color);
+            prev = Some(c);
         }
     }
 
+    fn ascent(&self) -> i32 {
+        self.font_bounding_box.height + self.font_bounding_box.y
+    }
+
+    fn draw_char(&self, canvas: &mut Canvas<Color>, x: i32, y: i32, c: char, color: Color) -> i32 {
+        if let Some(glyph) = self.glyphs.get(&(c as i32)) {
+            self.draw_glyph(canvas, x, y, glyph, color);
+            return glyph.dwidth;
+        }
+
+        // Fallback glyphs are aligned to the baseline of this font
+        for fallback in &self.fallbacks {
+            let fallback = fallback.lock();
+            if let Some(glyph) = fallback.glyphs.get(&(c as i32)) {
+                let y = y + self.ascent() - fallback.ascent();
+                fallback.draw_glyph(canvas, x, y, glyph, color);
+                return glyph.dwidth;
+            }
+        }
+
+        let Some((src_x, src_y)) = builtin_glyph_pos(c) else {
+            return 0;
+        };
+        let y = y + self.ascent() - BUILTIN_FONT_ASCENT;
+
+        // The target canvas can be the font image itself, which is then already locked
+        let mut bitmap = [[false; FONT_WIDTH as usize]; FONT_HEIGHT as usize];
+        let mut read_bitmap = |font_canvas: &Canvas<Color>| {
+            for (yi, row) in bitmap.iter_mut().enumerate() {
+                for (xi, bit) in row.iter_mut().enumerate() {
+                    *bit = font_canvas.read_data(src_x as usize + xi, src_y as usize + yi) == 1;
+                }
+            }
+        };
+        if let Some(font_image) = FONT_IMAGE.try_lock() {
+            read_bitmap(&font_image.canvas);
+        } else {
+            read_bitmap(canvas);
+        }
+
+        for (yi, row) in bitmap.iter().enumerate() {
+            for (xi, &bit) in row.iter().enumerate() {
+                let (value_x, value_y) = (x + xi as i32, y + yi as i32);
+                if bit && canvas.clip_rect.contains(value_x, value_y) {
+                    canvas.write_data(value_x as usize, value_y as usize, color);
+                }
+            }
+        }
+        FONT_WIDTH as i32
+    }
+
     fn draw_glyph(&self, canvas: &mut Canvas<Color>, x: i32, y: i32, glyph: &Glyph, color: Color) {
         let x = x + self.font_bounding_box.x + glyph.bbx.x;
         let y = y + self.font_bounding_box.y + self.font_bounding_box.height
@@ -152,3 +362,184 @@ impl Font {
         }
     }
 }
+
+pub(crate) fn builtin_glyph_pos(c: char) -> Option<(i32, i32)> {
+    let index = *FONT_GLYPHS.lock().get(&c)? as i32;
+    Some((
+        (index % NUM_FONT_ROWS as i32) * FONT_WIDTH as i32,
+        (index / NUM_FONT_ROWS as i32) * FONT_HEIGHT as i32,
+    ))
+}
+
+pub(crate) fn add_builtin_glyph(
+    glyphs: &mut HashMap<char, u32>,
+    font_canvas: &mut Canvas<Color>,
+    c: char,
+    data: &[&str],
+) -> Result<(), String> {
+    let rows: Vec<String> = data.iter().map(|row| utils::simplify_string(row)).collect();
+    if rows.is_empty()
+        || rows.len() > FONT_HEIGHT as usize
+        || rows.iter().any(|row| row.len() > FONT_WIDTH as usize)
+    {
+        return Err(format!(
+            "Glyph data must be up to {FONT_WIDTH}x{FONT_HEIGHT} pixels"
+        ));
+    }
+    if rows
+        .iter()
+        .any(|row| row.chars().any(|c| c != '0' && c != '1'))
+    {
+        return Err("Glyph data must consist of 0 and 1".to_string());
+    }
+
+    let index = match glyphs.get(&c) {
+        Some(index) => *index,
+        None if glyphs.len() < MAX_FONT_GLYPHS as usize => glyphs.len() as u32,
+        None => return Err("Maximum number of glyphs exceeded".to_string()),
+    };
+
+    // Pixels are written directly so the font image's draw state doesn't apply
+    let x = (index % NUM_FONT_ROWS * FONT_WIDTH) as usize;
+    let y = (index / NUM_FONT_ROWS * FONT_HEIGHT) as usize;
+    let stride = font_canvas.width() as usize;
+    for yi in 0..FONT_HEIGHT as usize {
+        for xi in 0..FONT_WIDTH as usize {
+            let bit = rows
+                .get(yi)
+                .and_then(|row| row.as_bytes().get(xi))
+                .is_some_and(|&value| value == b'1');
+            font_canvas.data[stride * (y + yi) + x + xi] = Color::from(bit);
+        }
+    }
+    glyphs.insert(c, index);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Image;
+    use crate::text_layout;
+
+    const BDF_TEXT: &str = "FONTBOUNDINGBOX 4 6 0 -1
+STARTCHAR A
+ENCODING 65
+DWIDTH 4 0
+BBX 3 2 0 0
+BITMAP
+A0
+40
+ENDCHAR
+KPX 65 86 -1
+";
+
+    #[test]
+    fn test_from_bdf() {
+        let font = Font::from_bdf(BDF_TEXT).unwrap();
+        assert_eq!(font.line_height(), 6);
+        assert_eq!(font.text_width("AA?"), 12);
+        assert_eq!(font.text_width("AV"), 7);
+        assert_eq!(font.glyphs[&65].bitmap, vec![0b101, 0b010]);
+    }
+
+    #[test]
+    fn test_from_bdf_error() {
+        let bdf_text = BDF_TEXT.replace("DWIDTH 4 0", "DWIDTH x");
+        assert_eq!(
+            Font::from_bdf(&bdf_text).err(),
+            Some("Failed to parse BDF line 4".to_string())
+        );
+        assert!(Font::from_ttf(b"not a font".to_vec(), 8.0).is_err());
+    }
+
+    #[test]
+    fn test_builtin_latin1_glyph() {
+        assert_eq!(text_layout::text_width("é", None), FONT_WIDTH as i32);
+        let (src_x, src_y) = builtin_glyph_pos('é').unwrap();
+        let image = Image::new(FONT_WIDTH, FONT_HEIGHT);
+        image.lock().text(0.0, 0.0, "é", 7, None);
+
+        let font_image = FONT_IMAGE.lock();
+        let mut num_pixels = 0;
+        for y in 0..FONT_HEIGHT as usize {
+            for x in 0..FONT_WIDTH as usize {
+                let bit = font_image
+                    .canvas
+                    .read_data(src_x as usize + x, src_y as usize + y);
+                let pixel = image.lock().canvas.read_data(x, y);
+                assert_eq!(pixel, if bit == 1 { 7 } else { 0 });
+                num_pixels += usize::from(bit);
+            }
+        }
+        assert!(num_pixels > 0);
+    }
+
+    #[test]
+    fn test_add_builtin_glyph() {
+        let mut glyphs = FONT_GLYPHS.lock().clone();
+        let num_glyphs = glyphs.len() as u32;
+        let mut canvas = Canvas::new(
+            FONT_WIDTH * NUM_FONT_ROWS,
+            FONT_HEIGHT * MAX_FONT_GLYPHS.div_ceil(NUM_FONT_ROWS),
+        );
+        add_builtin_glyph(&mut glyphs, &mut canvas, '\u{e000}', &["1001", "0110"]).unwrap();
+        assert_eq!(glyphs[&'\u{e000}'], num_glyphs);
+
+        let x = (num_glyphs % NUM_FONT_ROWS * FONT_WIDTH) as usize;
+        let y = (num_glyphs / NUM_FONT_ROWS * FONT_HEIGHT) as usize;
+        assert_eq!(canvas.read_data(x, y), 1);
+        assert_eq!(canvas.read_data(x + 1, y), 0);
+        assert_eq!(canvas.read_data(x + 1, y + 1), 1);
+        assert_eq!(canvas.read_data(x, y + 2), 0);
+
+        assert!(add_builtin_glyph(&mut glyphs, &mut canvas, 'x', &["10101"]).is_err());
+        assert!(add_builtin_glyph(&mut glyphs, &mut canvas, 'x', &["2"]).is_err());
+
+        // Existing glyphs can still be replaced once the table is full
+        let mut code = 0xe001;
+        while glyphs.len() < MAX_FONT_GLYPHS as usize {
+            glyphs.insert(char::from_u32(code).unwrap(), glyphs.len() as u32);
+            code += 1;
+        }
+        assert_eq!(
+            add_builtin_glyph(&mut glyphs, &mut canvas, '\u{f000}', &["1"]),
+            Err("Maximum number of glyphs exceeded".to_string())
+        );
+        assert!(add_builtin_glyph(&mut glyphs, &mut canvas, '\u{e000}', &["1"]).is_ok());
+    }
+
+    #[test]
+    fn test_fallback_cycle() {
+        let font1 = new_shared_type!(Font::from_bdf(BDF_TEXT).unwrap());
+        let font2 = new_shared_type!(Font::from_bdf(BDF_TEXT).unwrap());
+        let font3 = new_shared_type!(Font::from_bdf(BDF_TEXT).unwrap());
+        font1.lock().set_fallbacks(vec![font2.clone()]).unwrap();
+        font2.lock().set_fallbacks(vec![font3.clone()]).unwrap();
+        assert!(font3.lock().set_fallbacks(vec![font1.clone()]).is_err());
+        assert!(font1.lock().set_fallbacks(vec![font1.clone()]).is_err());
+        assert_eq!(font1.lock().char_width('A'), 4);
+    }
+
+    #[test]
+    fn test_draw_into_font_image() {
+        let font = Font::from_bdf(BDF_TEXT).unwrap();
+        let (src_x, src_y) = builtin_glyph_pos('#').unwrap();
+        let mut canvas = Canvas::new(FONT_WIDTH, FONT_HEIGHT);
+        font.draw(&mut canvas, 0, 0, "#", 1);
+
+        // Drawing a glyph onto itself in the locked font image must not deadlock
+        let mut font_image = FONT_IMAGE.lock();
+        font.draw(&mut font_image.canvas, src_x, src_y, "#", 1);
+        for y in 0..FONT_HEIGHT as usize {
+            for x in 0..FONT_WIDTH as usize {
+                assert_eq!(
+                    font_image
+                        .canvas
+                        .read_data(src_x as usize + x, src_y as usize + y),
+                    canvas.read_data(x, y)
+                );
+            }
+        }
+    }
+}
@@ -4,10 +4,12 @@ use std::mem::size_of;
 use cfg_if::cfg_if;
 use glow::{HasContext, PixelUnpackData};
 
-use crate::font::SharedFont;
-use crate::image::Color;
-use crate::pyxel::Pyxel;
+use crate::canvas::StencilMode;
+use crate::font::{self, SharedFont};
+use crate::image::{Color, Rgb24, SharedImage};
+use crate::pyxel::{Pyxel, FONT_GLYPHS};
 use crate::settings::{BACKGROUND_COLOR, MAX_COLORS, NUM_SCREEN_TYPES};
+use crate::text_layout::{self, TextAlign};
 
 cfg_if! {
     if #[cfg(target_os = "macos")] {
@@ -32,10 +34,27 @@ pub struct ScreenShader {
     vertex_array: glow::VertexArray,
 }
 
+struct PaletteCycle {
+    start: Color,
+    end: Color,
+    interval: u32,
+    reverse: bool,
+    original_colors: Vec<Rgb24>,
+}
+
+impl PaletteCycle {
+    fn restore(&self, colors: &mut [Rgb24]) {
+        if let Some(range) = colors.get_mut(self.start as usize..=self.end as usize) {
+            range.copy_from_slice(&self.original_colors);
+        }
+    }
+}
+
 pub struct Graphics {
     screen_shaders: Vec<ScreenShader>,
     screen_texture: glow::NativeTexture,
     colors_texture: glow::NativeTexture,
+    palette_cycles: Vec<PaletteCycle>,
 }
 
 impl Graphics {
@@ -53,6 +72,7 @@ impl Graphics {
                 screen_shaders,
                 screen_texture,
                 colors_texture,
+                palette_cycles: Vec::new(),
             }
         }
     }
@@ -241,10 +261,85 @@ impl Pyxel {
         self.screen.lock().pal0();
     }
 
+    pub fn pal_cycle(&mut self, start: Color, end: Color, interval: u32, reverse: Option<bool>) {
+        let mut colors = self.colors.lock();
+        if start >= end || end as usize >= colors.len() {
+            return;
+        }
+
+        // Overlapping cycles are undone so the snapshot holds the unrotated colors
+        let (overlapping, cycles): (Vec<_>, Vec<_>) = self
+            .graphics
+            .palette_cycles
+            .drain(..)
+            .partition(|cycle| cycle.end >= start && cycle.start <= end);
+        self.graphics.palette_cycles = cycles;
+        for cycle in overlapping.iter().rev() {
+            cycle.restore(&mut colors);
+        }
+
+        self.graphics.palette_cycles.push(PaletteCycle {
+            start,
+            end,
+            interval: interval.max(1),
+            reverse: reverse.unwrap_or(false),
+            original_colors: colors[start as usize..=end as usize].to_vec(),
+        });
+    }
+
+    pub fn pal_cycle0(&mut self) {
+        let mut colors = self.colors.lock();
+        for cycle in self.graphics.palette_cycles.drain(..).rev() {
+            cycle.restore(&mut colors);
+        }
+    }
+
+    pub(crate) fn update_palette_cycles(&mut self) {
+        let mut colors = self.colors.lock();
+        for cycle in &self.graphics.palette_cycles {
+            if !self.frame_count.is_multiple_of(cycle.interval)
+                || cycle.end as usize >= colors.len()
+            {
+                continue;
+            }
+
+            let range = &mut colors[cycle.start as usize..=cycle.end as usize];
+            if cycle.reverse {
+                range.rotate_left(1);
+            } else {
+                range.rotate_right(1);
+            }
+        }
+    }
+
     pub fn dither(&self, alpha: f32) {
         self.screen.lock().dither(alpha);
     }
 
+    pub fn stencil(&self, image: SharedImage, transparent: Option<Color>) {
+        self.screen.lock().stencil(image, transparent);
+    }
+
+    pub fn stencil0(&self) {
+        self.screen.lock().stencil0();
+    }
+
+    pub fn stencil_mode(&self, mode: StencilMode) {
+        self.screen.lock().stencil_mode(mode);
+    }
+
+    pub fn stencil_set(&self) {
+        self.screen.lock().stencil_set();
+    }
+
+    pub fn stencil_clear(&self) {
+        self.screen.lock().stencil_clear();
+    }
+
+    pub fn stencil_invert(&self) {
+        self.screen.lock().stencil_invert();
+    }
+
     pub fn cls(&self, color: Color) {
         self.screen.lock().cls(color);
     }
@@ -297,6 +392,24 @@ impl Pyxel {
         self.screen.lock().fill(x, y, color);
     }
 
+    pub fn linear_gradient(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        angle: f64,
+        colors: &[Color],
+    ) {
+        self.screen
+            .lock()
+            .linear_gradient(x, y, width, height, angle, colors);
+    }
+
+    pub fn radial_gradient(&self, x: f64, y: f64, radius: f64, colors: &[Color]) {
+        self.screen.lock().radial_gradient(x, y, radius, colors);
+    }
+
     pub fn blt(
         &self,
         x: f64,
@@ -324,6 +437,128 @@ impl Pyxel {
         );
     }
 
+    pub fn blt_stretch(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        image_index: u32,
+        image_x: f64,
+        image_y: f64,
+        image_width: f64,
+        image_height: f64,
+        color_key: Option<Color>,
+        shear: Option<f64>,
+    ) {
+        self.screen.lock().blt_stretch(
+            x,
+            y,
+            width,
+            height,
+            self.images.lock()[image_index as usize].clone(),
+            image_x,
+            image_y,
+            image_width,
+            image_height,
+            color_key,
+            shear,
+        );
+    }
+
+    pub fn blt_scale(
+        &self,
+        x: f64,
+        y: f64,
+        image_index: u32,
+        image_x: f64,
+        image_y: f64,
+        width: f64,
+        height: f64,
+        color_key: Option<Color>,
+        scale_x: f64,
+        scale_y: f64,
+        shear: Option<f64>,
+    ) {
+        self.screen.lock().blt_scale(
+            x,
+            y,
+            self.images.lock()[image_index as usize].clone(),
+            image_x,
+            image_y,
+            width,
+            height,
+            color_key,
+            scale_x,
+            scale_y,
+            shear,
+        );
+    }
+
+    pub fn blt_tiled(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        image_index: u32,
+        image_x: f64,
+        image_y: f64,
+        tile_width: f64,
+        tile_height: f64,
+        color_key: Option<Color>,
+    ) {
+        self.screen.lock().blt_tiled(
+            x,
+            y,
+            width,
+            height,
+            self.images.lock()[image_index as usize].clone(),
+            image_x,
+            image_y,
+            tile_width,
+            tile_height,
+            color_key,
+        );
+    }
+
+    pub fn blt9(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        image_index: u32,
+        image_x: f64,
+        image_y: f64,
+        image_width: f64,
+        image_height: f64,
+        left: f64,
+        top: f64,
+        right: f64,
+        bottom: f64,
+        color_key: Option<Color>,
+        tiled: Option<bool>,
+    ) {
+        self.screen.lock().blt9(
+            x,
+            y,
+            width,
+            height,
+            self.images.lock()[image_index as usize].clone(),
+            image_x,
+            image_y,
+            image_width,
+            image_height,
+            left,
+            top,
+            right,
+            bottom,
+            color_key,
+            tiled,
+        );
+    }
+
     pub fn bltm(
         &self,
         x: f64,
@@ -348,6 +583,7 @@ impl Pyxel {
             color_key,
             rotate,
             scale,
+            Some(self.frame_count),
         );
     }
 
@@ -355,6 +591,61 @@ impl Pyxel {
         self.screen.lock().text(x, y, string, color, font);
     }
 
+    pub fn text_rich(&self, x: f64, y: f64, string: &str, color: Color, font: Option<SharedFont>) {
+        self.screen
+            .lock()
+            .text_rich(x, y, string, color, font, Some(self.frame_count));
+    }
+
+    pub fn text_width(string: &str, font: Option<SharedFont>) -> i32 {
+        let font = font.as_ref().map(|font| font.lock());
+        text_layout::text_width(string, font.as_deref())
+    }
+
+    pub fn text_bounds(
+        string: &str,
+        font: Option<SharedFont>,
+        line_spacing: Option<i32>,
+    ) -> (i32, i32) {
+        let font = font.as_ref().map(|font| font.lock());
+        text_layout::text_bounds(string, font.as_deref(), line_spacing.unwrap_or(0))
+    }
+
+    pub fn text_box(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        string: &str,
+        color: Color,
+        font: Option<SharedFont>,
+        align: Option<TextAlign>,
+        line_spacing: Option<i32>,
+        wrap: Option<bool>,
+        ellipsis: Option<bool>,
+    ) {
+        self.screen.lock().text_box(
+            x,
+            y,
+            width,
+            height,
+            string,
+            color,
+            font,
+            align,
+            line_spacing,
+            wrap,
+            ellipsis,
+        );
+    }
+
+    pub fn add_glyph(&self, c: char, data: &[&str]) -> Result<(), String> {
+        let mut font_glyphs = FONT_GLYPHS.lock();
+        let mut font = self.font.lock();
+        font::add_builtin_glyph(&mut font_glyphs, &mut font.canvas, c, data)
+    }
+
     pub(crate) fn render_screen(&mut self) {
         unsafe {
             let gl = pyxel_platform::glow_context();
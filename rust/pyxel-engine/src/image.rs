@@ -1,16 +1,18 @@
 use std::array;
-use std::collections::HashMap;
 use std::path::Path;
 
 use image::imageops;
 
-use crate::canvas::{Canvas, CopyArea, ToIndex};
-use crate::font::SharedFont;
+use crate::canvas::{Canvas, CopyArea, StencilMode, ToIndex};
+use crate::font::{builtin_glyph_pos, SharedFont};
+use crate::image_transform::Upscale;
+use crate::indexed_png::IndexedPng;
 use crate::pyxel::{COLORS, FONT_IMAGE, IMAGES};
+use crate::quantizer::{self, Dither, Quantize, Rgb};
 use crate::rect_area::RectArea;
-use crate::settings::{
-    FONT_HEIGHT, FONT_WIDTH, MAX_COLORS, MAX_FONT_CODE, MIN_FONT_CODE, NUM_FONT_ROWS, TILE_SIZE,
-};
+use crate::rich_text::{self, RichToken, TextEffect};
+use crate::settings::{FONT_HEIGHT, FONT_WIDTH, MAX_COLORS, TILE_SIZE};
+use crate::text_layout::{self, TextAlign};
 use crate::tilemap::{ImageSource, SharedTilemap};
 use crate::utils;
 
@@ -38,11 +40,25 @@ impl Image {
         })
     }
 
-    pub fn from_image(filename: &str, include_colors: Option<bool>) -> SharedImage {
+    pub fn from_image(
+        filename: &str,
+        include_colors: Option<bool>,
+        dither: Option<Dither>,
+        quantize: Option<Quantize>,
+        num_colors: Option<u32>,
+        transparent: Option<Color>,
+    ) -> SharedImage {
         let include_colors = include_colors.unwrap_or(false);
-        let mut colors = COLORS.lock();
-        if include_colors {
-            colors.clear();
+        let dither = dither.unwrap_or(Dither::None);
+        let quantize = quantize.unwrap_or(Quantize::MedianCut);
+        let num_colors = num_colors.unwrap_or(MAX_COLORS).clamp(1, MAX_COLORS) as usize;
+        if transparent.is_some_and(|transparent| transparent as u32 >= MAX_COLORS) {
+            println!("Transparent color must be less than {MAX_COLORS}");
+            return Self::new(1, 1);
+        }
+
+        if let Some(image) = Self::from_indexed_png(filename, include_colors, transparent) {
+            return image;
         }
 
         let file = image::open(Path::new(&filename));
@@ -51,59 +67,92 @@ impl Image {
             return Self::new(1, 1);
         }
 
-        let file_image = file.unwrap().to_rgb8();
+        let file_image = file.unwrap().to_rgba8();
         let (width, height) = file_image.dimensions();
-        let image = Self::new(width, height);
-
-        {
-            let mut image = image.lock();
-            let mut color_table = HashMap::<(u8, u8, u8), Color>::new();
+        let pixels: Vec<Option<Rgb>> = file_image
+            .pixels()
+            .map(|p| {
+                if transparent.is_some() && p[3] < 128 {
+                    None
+                } else {
+                    Some((p[0], p[1], p[2]))
+                }
+            })
+            .collect();
 
-            for y in 0..height {
-                for x in 0..width {
-                    let p = file_image.get_pixel(x, y);
-                    let src_rgb = (p[0], p[1], p[2]);
-
-                    if let Some(color) = color_table.get(&src_rgb) {
-                        image.canvas.write_data(x as usize, y as usize, *color);
-                    } else {
-                        let mut closest_color: Color = 0;
-
-                        if include_colors {
-                            colors.push(
-                                ((src_rgb.0 as u32) << 16)
-                                    | ((src_rgb.1 as u32) << 8)
-                                    | src_rgb.2 as u32,
-                            );
-                            closest_color = colors.len() as Color - 1;
-                        } else {
-                            let mut closest_dist: f64 = f64::MAX;
-                            for (i, pal_color) in colors.iter().enumerate() {
-                                let pal_rgb = (
-                                    (pal_color >> 16) as u8,
-                                    (pal_color >> 8) as u8,
-                                    *pal_color as u8,
-                                );
-                                let dist = Self::color_dist(src_rgb, pal_rgb);
-                                if dist < closest_dist {
-                                    closest_color = i as Color;
-                                    closest_dist = dist;
-                                }
-                            }
-                        }
+        let mut colors = COLORS.lock();
+        if include_colors {
+            // One entry is reserved for the transparent color so the palette stays in range
+            let num_colors = num_colors - usize::from(transparent.is_some() && num_colors > 1);
+            colors.clear();
+            colors.extend(
+                quantizer::generate_palette(&pixels, num_colors, quantize)
+                    .into_iter()
+                    .map(quantizer::rgb_to_rgb24),
+            );
 
-                        color_table.insert(src_rgb, closest_color);
-                        image
-                            .canvas
-                            .write_data(x as usize, y as usize, closest_color);
-                    }
+            // The transparent color gets its own entry so opaque pixels never map to it
+            if let Some(transparent) = transparent {
+                let transparent = transparent as usize;
+                if colors.len() < transparent {
+                    colors.resize(transparent, 0);
                 }
+                colors.insert(transparent, 0);
             }
         }
 
+        let palette: Vec<Rgb> = colors
+            .iter()
+            .map(|rgb| quantizer::rgb24_to_rgb(*rgb))
+            .collect();
+        let image = Self::new(width, height);
+        image.lock().canvas.data = quantizer::remap(&pixels, width, &palette, transparent, dither);
         image
     }
 
+    fn from_indexed_png(
+        filename: &str,
+        include_colors: bool,
+        transparent: Option<Color>,
+    ) -> Option<SharedImage> {
+        let png = IndexedPng::load(filename)?;
+
+        // Index 255 has no palette slot, so such files go through color conversion instead
+        if png
+            .transparent
+            .is_some_and(|color| color as u32 >= MAX_COLORS)
+            || png.data.iter().any(|&color| color as u32 >= MAX_COLORS)
+        {
+            return None;
+        }
+
+        let mut colors = COLORS.lock();
+
+        // Indices are kept as is only when they refer to the same colors
+        if include_colors {
+            colors.clear();
+            colors.extend(png.palette.iter().take(MAX_COLORS as usize));
+        } else if png.data.iter().any(|&color| {
+            png.transparent != Some(color)
+                && colors.get(color as usize) != png.palette.get(color as usize)
+        }) {
+            return None;
+        }
+
+        let image = Self::new(png.width, png.height);
+        image.lock().canvas.data = png
+            .data
+            .iter()
+            .map(|&color| match (png.transparent, transparent) {
+                (Some(png_transparent), Some(transparent)) if color == png_transparent => {
+                    transparent
+                }
+                _ => color,
+            })
+            .collect();
+        Some(image)
+    }
+
     pub const fn width(&self) -> u32 {
         self.canvas.width()
     }
@@ -149,8 +198,25 @@ impl Image {
         );
     }
 
-    pub fn load(&mut self, x: i32, y: i32, filename: &str, include_colors: Option<bool>) {
-        let image = Self::from_image(filename, include_colors);
+    pub fn load(
+        &mut self,
+        x: i32,
+        y: i32,
+        filename: &str,
+        include_colors: Option<bool>,
+        dither: Option<Dither>,
+        quantize: Option<Quantize>,
+        num_colors: Option<u32>,
+        transparent: Option<Color>,
+    ) {
+        let image = Self::from_image(
+            filename,
+            include_colors,
+            dither,
+            quantize,
+            num_colors,
+            transparent,
+        );
         let width = image.lock().width();
         let height = image.lock().height();
 
@@ -168,10 +234,55 @@ impl Image {
         );
     }
 
-    pub fn save(&self, filename: &str, scale: u32) {
+    pub fn save(
+        &self,
+        filename: &str,
+        scale: u32,
+        indexed: Option<bool>,
+        transparent: Option<Color>,
+        upscale: Option<Upscale>,
+    ) {
+        if let Some(upscale) = upscale.filter(|upscale| *upscale != Upscale::None) {
+            let image = self.upscaled(upscale);
+            image
+                .lock()
+                .save(filename, scale, indexed, transparent, None);
+            return;
+        }
+
         let colors = COLORS.lock();
         let width = self.width();
         let height = self.height();
+        let filename = utils::add_file_extension(filename, ".png");
+
+        if indexed.unwrap_or(false) {
+            let mut palette = colors.clone();
+            if let Some(transparent) = transparent {
+                if palette.len() <= transparent as usize {
+                    palette.resize(transparent as usize + 1, 0);
+                }
+            }
+
+            let data = (0..height * scale)
+                .flat_map(|y| {
+                    (0..width * scale).map(move |x| {
+                        self.canvas
+                            .read_data((x / scale) as usize, (y / scale) as usize)
+                    })
+                })
+                .collect();
+            let png = IndexedPng {
+                width: width * scale,
+                height: height * scale,
+                palette,
+                transparent,
+                data,
+            };
+            png.save(&filename)
+                .unwrap_or_else(|message| panic!("{message}"));
+            return;
+        }
+
         let mut image = image::RgbImage::new(width, height);
 
         for y in 0..height {
@@ -190,7 +301,6 @@ impl Image {
             height * scale,
             imageops::FilterType::Nearest,
         );
-        let filename = utils::add_file_extension(filename, ".png");
         image
             .save(&filename)
             .unwrap_or_else(|_| panic!("Failed to open file '{filename}'"));
@@ -226,6 +336,50 @@ impl Image {
         self.canvas.dither(alpha);
     }
 
+    pub fn stencil(&mut self, image: SharedImage, transparent: Option<Color>) {
+        if let Some(image) = image.try_lock() {
+            self.canvas.stencil_from(&image.canvas, transparent);
+        } else {
+            let width = self.width();
+            let height = self.height();
+            let mut canvas = Canvas::new(width, height);
+
+            canvas.blt(
+                0.0,
+                0.0,
+                &self.canvas,
+                0.0,
+                0.0,
+                width as f64,
+                height as f64,
+                None,
+                None,
+            );
+            self.canvas.stencil_from(&canvas, transparent);
+        }
+        self.canvas.set_stencil_mode(StencilMode::Test);
+    }
+
+    pub fn stencil0(&mut self) {
+        self.canvas.set_stencil_mode(StencilMode::Off);
+    }
+
+    pub fn stencil_mode(&mut self, mode: StencilMode) {
+        self.canvas.set_stencil_mode(mode);
+    }
+
+    pub fn stencil_set(&mut self) {
+        self.canvas.stencil_fill(true);
+    }
+
+    pub fn stencil_clear(&mut self) {
+        self.canvas.stencil_fill(false);
+    }
+
+    pub fn stencil_invert(&mut self) {
+        self.canvas.stencil_invert();
+    }
+
     pub fn cls(&mut self, color: Color) {
         self.canvas.cls(self.palette[color as usize]);
     }
@@ -286,6 +440,25 @@ impl Image {
         self.canvas.fill(x, y, self.palette[color as usize]);
     }
 
+    pub fn linear_gradient(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        angle: f64,
+        colors: &[Color],
+    ) {
+        let ramp: Vec<Color> = colors.iter().map(|&c| self.palette[c as usize]).collect();
+        self.canvas
+            .linear_gradient(x, y, width, height, angle, &ramp);
+    }
+
+    pub fn radial_gradient(&mut self, x: f64, y: f64, radius: f64, colors: &[Color]) {
+        let ramp: Vec<Color> = colors.iter().map(|&c| self.palette[c as usize]).collect();
+        self.canvas.radial_gradient(x, y, radius, &ramp);
+    }
+
     pub fn blt(
         &mut self,
         x: f64,
@@ -360,6 +533,250 @@ impl Image {
         }
     }
 
+    pub fn blt_stretch(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        image: SharedImage,
+        image_x: f64,
+        image_y: f64,
+        image_width: f64,
+        image_height: f64,
+        transparent: Option<Color>,
+        shear: Option<f64>,
+    ) {
+        let shear = shear.unwrap_or(0.0);
+        if let Some(image) = image.try_lock() {
+            self.canvas.blt_stretch(
+                x,
+                y,
+                width,
+                height,
+                &image.canvas,
+                image_x,
+                image_y,
+                image_width,
+                image_height,
+                transparent,
+                Some(&self.palette),
+                shear,
+            );
+        } else {
+            let canvas = self.copy_canvas(image_x, image_y, image_width.abs(), image_height.abs());
+            self.canvas.blt_stretch(
+                x,
+                y,
+                width,
+                height,
+                &canvas,
+                0.0,
+                0.0,
+                image_width,
+                image_height,
+                transparent,
+                Some(&self.palette),
+                shear,
+            );
+        }
+    }
+
+    pub fn blt_scale(
+        &mut self,
+        x: f64,
+        y: f64,
+        image: SharedImage,
+        image_x: f64,
+        image_y: f64,
+        width: f64,
+        height: f64,
+        transparent: Option<Color>,
+        scale_x: f64,
+        scale_y: f64,
+        shear: Option<f64>,
+    ) {
+        let dst_width = width.abs() * scale_x.abs();
+        let dst_height = height.abs() * scale_y.abs();
+        let sign_x = if scale_x < 0.0 { -1.0 } else { 1.0 };
+        let sign_y = if scale_y < 0.0 { -1.0 } else { 1.0 };
+        self.blt_stretch(
+            x + (width.abs() - dst_width) / 2.0,
+            y + (height.abs() - dst_height) / 2.0,
+            dst_width,
+            dst_height,
+            image,
+            image_x,
+            image_y,
+            width * sign_x,
+            height * sign_y,
+            transparent,
+            shear,
+        );
+    }
+
+    pub fn blt_tiled(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        image: SharedImage,
+        image_x: f64,
+        image_y: f64,
+        tile_width: f64,
+        tile_height: f64,
+        transparent: Option<Color>,
+    ) {
+        if let Some(image) = image.try_lock() {
+            self.canvas.blt_tiled(
+                x,
+                y,
+                width,
+                height,
+                &image.canvas,
+                image_x,
+                image_y,
+                tile_width,
+                tile_height,
+                transparent,
+                Some(&self.palette),
+            );
+        } else {
+            let canvas = self.copy_canvas(image_x, image_y, tile_width, tile_height);
+            self.canvas.blt_tiled(
+                x,
+                y,
+                width,
+                height,
+                &canvas,
+                0.0,
+                0.0,
+                tile_width,
+                tile_height,
+                transparent,
+                Some(&self.palette),
+            );
+        }
+    }
+
+    pub fn blt9(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        image: SharedImage,
+        image_x: f64,
+        image_y: f64,
+        image_width: f64,
+        image_height: f64,
+        left: f64,
+        top: f64,
+        right: f64,
+        bottom: f64,
+        transparent: Option<Color>,
+        tiled: Option<bool>,
+    ) {
+        let tiled = tiled.unwrap_or(false);
+        let canvas = if let Some(image) = image.try_lock() {
+            Self::copy_canvas_from(&image.canvas, image_x, image_y, image_width, image_height)
+        } else {
+            self.copy_canvas(image_x, image_y, image_width, image_height)
+        };
+
+        let dst_xs = Self::nine_slice_spans(x, width, left, right);
+        let dst_ys = Self::nine_slice_spans(y, height, top, bottom);
+        let src_xs = Self::nine_slice_spans(0.0, image_width, left, right);
+        let src_ys = Self::nine_slice_spans(0.0, image_height, top, bottom);
+
+        for row in 0..3 {
+            let (dst_y, dst_height) = dst_ys[row];
+            let (src_y, src_height) = src_ys[row];
+            if dst_height <= 0.0 || src_height <= 0.0 {
+                continue;
+            }
+
+            for col in 0..3 {
+                let (dst_x, dst_width) = dst_xs[col];
+                let (src_x, src_width) = src_xs[col];
+                if dst_width <= 0.0 || src_width <= 0.0 {
+                    continue;
+                }
+
+                if tiled {
+                    self.canvas.blt_tiled(
+                        dst_x,
+                        dst_y,
+                        dst_width,
+                        dst_height,
+                        &canvas,
+                        src_x,
+                        src_y,
+                        src_width,
+                        src_height,
+                        transparent,
+                        Some(&self.palette),
+                    );
+                } else {
+                    self.canvas.blt_stretch(
+                        dst_x,
+                        dst_y,
+                        dst_width,
+                        dst_height,
+                        &canvas,
+                        src_x,
+                        src_y,
+                        src_width,
+                        src_height,
+                        transparent,
+                        Some(&self.palette),
+                        0.0,
+                    );
+                }
+            }
+        }
+    }
+
+    fn nine_slice_spans(start: f64, size: f64, head: f64, tail: f64) -> [(f64, f64); 3] {
+        let head = head.min(size).max(0.0);
+        let tail = tail.min(size - head).max(0.0);
+        [
+            (start, head),
+            (start + head, size - head - tail),
+            (start + size - tail, tail),
+        ]
+    }
+
+    fn copy_canvas(&self, image_x: f64, image_y: f64, width: f64, height: f64) -> Canvas<Color> {
+        Self::copy_canvas_from(&self.canvas, image_x, image_y, width, height)
+    }
+
+    fn copy_canvas_from(
+        canvas: &Canvas<Color>,
+        image_x: f64,
+        image_y: f64,
+        width: f64,
+        height: f64,
+    ) -> Canvas<Color> {
+        let copy_width = utils::f64_to_u32(width.abs());
+        let copy_height = utils::f64_to_u32(height.abs());
+        let mut copy = Canvas::new(copy_width, copy_height);
+
+        copy.blt(
+            0.0,
+            0.0,
+            canvas,
+            image_x,
+            image_y,
+            copy_width as f64,
+            copy_height as f64,
+            None,
+            None,
+        );
+        copy
+    }
+
     fn blt_transform(
         &mut self,
         x: f64,
@@ -434,6 +851,7 @@ impl Image {
         transparent: Option<Color>,
         rotate: Option<f64>,
         scale: Option<f64>,
+        time: Option<u32>,
     ) {
         let rotate = rotate.unwrap_or(0.0);
         let scale = scale.unwrap_or(1.0);
@@ -449,6 +867,7 @@ impl Image {
                 transparent,
                 rotate,
                 scale,
+                time,
             );
             return;
         }
@@ -461,6 +880,7 @@ impl Image {
         let height = utils::f64_to_i32(height);
 
         let tilemap = tilemap.lock();
+        let time = time.filter(|_| !tilemap.animations.is_empty());
         let tilemap_rect = RectArea::new(
             tilemap.canvas.self_rect.left() * TILE_SIZE as i32,
             tilemap.canvas.self_rect.top() * TILE_SIZE as i32,
@@ -499,6 +919,8 @@ impl Image {
             ImageSource::Image(image) => image.lock(),
         };
 
+        // Tiles are resolved once and reused for the pixels they cover
+        let mut cached_tile = None;
         for yi in 0..height {
             for xi in 0..width {
                 let tilemap_x = src_x + sign_x * xi + offset_x;
@@ -506,7 +928,17 @@ impl Image {
 
                 let tile_x = tilemap_x / TILE_SIZE as i32;
                 let tile_y = tilemap_y / TILE_SIZE as i32;
-                let tile = tilemap.canvas.read_data(tile_x as usize, tile_y as usize);
+                let tile = match cached_tile {
+                    Some((coord, tile)) if coord == (tile_x, tile_y) => tile,
+                    _ => {
+                        let mut tile = tilemap.canvas.read_data(tile_x as usize, tile_y as usize);
+                        if let Some(time) = time {
+                            tile = tilemap.animated_tile(tile, time);
+                        }
+                        cached_tile = Some(((tile_x, tile_y), tile));
+                        tile
+                    }
+                };
 
                 let value_x = tile.0 as i32 * TILE_SIZE as i32 + tilemap_x % TILE_SIZE as i32;
                 if value_x < 0 || value_x >= image.width() as i32 {
@@ -542,6 +974,7 @@ impl Image {
         transparent: Option<Color>,
         rotate: f64,
         scale: f64,
+        time: Option<u32>,
     ) {
         let copy_width = utils::f64_to_u32(width.abs());
         let copy_height = utils::f64_to_u32(height.abs());
@@ -562,6 +995,7 @@ impl Image {
                 None,
                 None,
                 None,
+                time,
             );
             image.clip(
                 -tilemap_x,
@@ -608,13 +1042,9 @@ impl Image {
                 y += FONT_HEIGHT as i32;
                 continue;
             }
-            if c < MIN_FONT_CODE || c > MAX_FONT_CODE {
+            let Some((src_x, src_y)) = builtin_glyph_pos(c) else {
                 continue;
-            }
-
-            let code = c as i32 - MIN_FONT_CODE as i32;
-            let src_x = (code % NUM_FONT_ROWS as i32) * FONT_WIDTH as i32;
-            let src_y = (code / NUM_FONT_ROWS as i32) * FONT_HEIGHT as i32;
+            };
 
             self.blt(
                 x as f64,
@@ -633,13 +1063,202 @@ impl Image {
         self.pal(1, palette1);
     }
 
-    fn color_dist(rgb1: (u8, u8, u8), rgb2: (u8, u8, u8)) -> f64 {
-        let (r1, g1, b1) = rgb1;
-        let (r2, g2, b2) = rgb2;
-        let dx = (r1 as f64 - r2 as f64) * 0.30;
-        let dy = (g1 as f64 - g2 as f64) * 0.59;
-        let dz = (b1 as f64 - b2 as f64) * 0.11;
+    pub fn text_box(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        string: &str,
+        color: Color,
+        font: Option<SharedFont>,
+        align: Option<TextAlign>,
+        line_spacing: Option<i32>,
+        wrap: Option<bool>,
+        ellipsis: Option<bool>,
+    ) {
+        let lines = {
+            let font = font.as_ref().map(|font| font.lock());
+            text_layout::layout_text(
+                string,
+                utils::f64_to_i32(width),
+                utils::f64_to_i32(height),
+                font.as_deref(),
+                align.unwrap_or(TextAlign::Left),
+                line_spacing.unwrap_or(0),
+                wrap.unwrap_or(true),
+                ellipsis.unwrap_or(false),
+            )
+        };
 
-        dx * dx + dy * dy + dz * dz
+        for (offset_x, offset_y, line) in lines {
+            self.text(
+                x + offset_x as f64,
+                y + offset_y as f64,
+                &line,
+                color,
+                font.clone(),
+            );
+        }
+    }
+
+    pub fn text_rich(
+        &mut self,
+        x: f64,
+        y: f64,
+        string: &str,
+        color: Color,
+        font: Option<SharedFont>,
+        frame_count: Option<u32>,
+    ) {
+        let frame_count = frame_count.unwrap_or(0);
+        let line_height = {
+            let font = font.as_ref().map(|font| font.lock());
+            text_layout::line_height(font.as_deref())
+        };
+        let mut colors = vec![color];
+        let mut effects = vec![TextEffect::None];
+        let mut cur_x = x;
+        let mut cur_y = y;
+        let mut char_index = 0;
+
+        for token in rich_text::parse_rich_text(string) {
+            match token {
+                RichToken::Char('\n') => {
+                    cur_x = x;
+                    cur_y += line_height as f64;
+                }
+                RichToken::Char(c) => {
+                    let effect = *effects.last().unwrap();
+                    let (dx, dy) = rich_text::effect_offset(effect, char_index, frame_count);
+                    let width = {
+                        let font = font.as_ref().map(|font| font.lock());
+                        text_layout::char_width(c, font.as_deref())
+                    };
+                    self.text(
+                        cur_x + dx as f64,
+                        cur_y + dy as f64,
+                        c.encode_utf8(&mut [0; 4]),
+                        *colors.last().unwrap(),
+                        font.clone(),
+                    );
+                    cur_x += width as f64;
+                    char_index += 1;
+                }
+                RichToken::Color(color) => colors.push(color),
+                RichToken::ColorEnd => {
+                    if colors.len() > 1 {
+                        colors.pop();
+                    }
+                }
+                RichToken::Icon(image_index, index) => {
+                    let Some(image) = IMAGES.lock().get(image_index as usize).cloned() else {
+                        continue;
+                    };
+                    let (u, v) = rich_text::icon_coord(index);
+                    self.blt(
+                        cur_x,
+                        cur_y,
+                        image,
+                        u as f64,
+                        v as f64,
+                        TILE_SIZE as f64,
+                        TILE_SIZE as f64,
+                        Some(0),
+                        None,
+                        None,
+                    );
+                    cur_x += TILE_SIZE as f64;
+                }
+                RichToken::Effect(effect) => effects.push(effect),
+                RichToken::EffectEnd => {
+                    if effects.len() > 1 {
+                        effects.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::Tilemap;
+
+    #[test]
+    fn test_bltm_animated_tiles() {
+        let source = Image::new(16, 8);
+        source.lock().rect(8.0, 0.0, 8.0, 8.0, 2);
+        let tilemap = Tilemap::new(2, 1, ImageSource::Image(source));
+        tilemap
+            .lock()
+            .set_animation((0, 0), &[((0, 0), 5), ((1, 0), 5)]);
+
+        let image = Image::new(16, 8);
+        let mut image = image.lock();
+        image.bltm(
+            0.0,
+            0.0,
+            tilemap.clone(),
+            0.0,
+            0.0,
+            16.0,
+            8.0,
+            None,
+            None,
+            None,
+            Some(0),
+        );
+        assert_eq!((image.pget(0.0, 0.0), image.pget(15.0, 7.0)), (0, 0));
+        image.bltm(
+            0.0,
+            0.0,
+            tilemap,
+            4.0,
+            0.0,
+            12.0,
+            8.0,
+            None,
+            None,
+            None,
+            Some(5),
+        );
+        assert_eq!((image.pget(0.0, 0.0), image.pget(11.0, 7.0)), (2, 2));
+    }
+
+    #[test]
+    fn test_from_image_transparent_range() {
+        let image = Image::from_image("unused.png", Some(true), None, None, None, Some(255));
+        let image = image.lock();
+        assert_eq!((image.width(), image.height()), (1, 1));
+    }
+
+    #[test]
+    fn test_from_indexed_png_full_palette() {
+        let filename = std::env::temp_dir().join("pyxel_image_full_palette_test.png");
+        let filename = filename.to_str().unwrap();
+        let colors = COLORS.lock().clone();
+        let mut palette: Vec<Rgb24> = (0..256).map(|i| i * 0x010101).collect();
+        palette[..colors.len()].copy_from_slice(&colors);
+        IndexedPng {
+            width: 4,
+            height: 1,
+            palette,
+            transparent: Some(255),
+            data: vec![1, 255, 3, 255],
+        }
+        .save(filename)
+        .unwrap();
+
+        let image = Image::from_image(filename, None, None, None, None, None);
+        std::fs::remove_file(filename).unwrap();
+        let mut image = image.lock();
+        assert!(image
+            .canvas
+            .data
+            .iter()
+            .all(|&color| (color as u32) < MAX_COLORS));
+        assert_eq!(image.pget(0.0, 0.0), 1);
     }
 }
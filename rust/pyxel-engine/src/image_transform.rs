@@ -0,0 +1,235 @@
+use crate::image::{Color, Image, SharedImage};
+use crate::settings::{UPSCALE_EPX, UPSCALE_SCALE2X, UPSCALE_SCALE3X};
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum Upscale {
+    None,
+    Scale2x,
+    Scale3x,
+    Epx,
+}
+
+impl Upscale {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            UPSCALE_SCALE2X => Self::Scale2x,
+            UPSCALE_SCALE3X => Self::Scale3x,
+            UPSCALE_EPX => Self::Epx,
+            _ => Self::None,
+        }
+    }
+}
+
+impl Image {
+    pub fn flipped_h(&self) -> SharedImage {
+        let width = self.width() as i32;
+        self.transformed(self.width(), self.height(), |x, y| {
+            self.clamped_pixel(width - 1 - x, y)
+        })
+    }
+
+    pub fn flipped_v(&self) -> SharedImage {
+        let height = self.height() as i32;
+        self.transformed(self.width(), self.height(), |x, y| {
+            self.clamped_pixel(x, height - 1 - y)
+        })
+    }
+
+    pub fn rotate90(&self) -> SharedImage {
+        let height = self.height() as i32;
+        self.transformed(self.height(), self.width(), |x, y| {
+            self.clamped_pixel(y, height - 1 - x)
+        })
+    }
+
+    pub fn crop(&self, x: i32, y: i32, width: u32, height: u32) -> SharedImage {
+        self.transformed(width, height, |cx, cy| {
+            let (sx, sy) = (x + cx, y + cy);
+            if sx < 0 || sy < 0 || sx >= self.width() as i32 || sy >= self.height() as i32 {
+                0
+            } else {
+                self.canvas.read_data(sx as usize, sy as usize)
+            }
+        })
+    }
+
+    pub fn resize(&self, width: u32, height: u32) -> SharedImage {
+        let scale_x = self.width() as f64 / width.max(1) as f64;
+        let scale_y = self.height() as f64 / height.max(1) as f64;
+        self.transformed(width, height, |x, y| {
+            self.clamped_pixel((x as f64 * scale_x) as i32, (y as f64 * scale_y) as i32)
+        })
+    }
+
+    pub fn scale2x(&self) -> SharedImage {
+        self.transformed(self.width() * 2, self.height() * 2, |x, y| {
+            let (top, left, center, right, bottom) = self.neighbors(x / 2, y / 2);
+            if top == bottom || left == right {
+                return center;
+            }
+            match (x % 2, y % 2) {
+                (0, 0) if left == top => left,
+                (1, 0) if top == right => right,
+                (0, 1) if left == bottom => left,
+                (1, 1) if bottom == right => right,
+                _ => center,
+            }
+        })
+    }
+
+    pub fn scale3x(&self) -> SharedImage {
+        self.transformed(self.width() * 3, self.height() * 3, |x, y| {
+            let (sx, sy) = (x / 3, y / 3);
+            let (top, left, center, right, bottom) = self.neighbors(sx, sy);
+            if top == bottom || left == right {
+                return center;
+            }
+            let top_left = self.clamped_pixel(sx - 1, sy - 1);
+            let top_right = self.clamped_pixel(sx + 1, sy - 1);
+            let bottom_left = self.clamped_pixel(sx - 1, sy + 1);
+            let bottom_right = self.clamped_pixel(sx + 1, sy + 1);
+            match (x % 3, y % 3) {
+                (0, 0) if left == top => left,
+                (1, 0)
+                    if (left == top && center != top_right)
+                        || (top == right && center != top_left) =>
+                {
+                    top
+                }
+                (2, 0) if top == right => right,
+                (0, 1)
+                    if (left == top && center != bottom_left)
+                        || (left == bottom && center != top_left) =>
+                {
+                    left
+                }
+                (2, 1)
+                    if (top == right && center != bottom_right)
+                        || (bottom == right && center != top_right) =>
+                {
+                    right
+                }
+                (0, 2) if left == bottom => left,
+                (1, 2)
+                    if (left == bottom && center != bottom_right)
+                        || (bottom == right && center != bottom_left) =>
+                {
+                    bottom
+                }
+                (2, 2) if bottom == right => right,
+                _ => center,
+            }
+        })
+    }
+
+    pub fn epx(&self) -> SharedImage {
+        self.transformed(self.width() * 2, self.height() * 2, |x, y| {
+            let (top, left, center, right, bottom) = self.neighbors(x / 2, y / 2);
+            let pairs = [
+                top == left,
+                top == right,
+                top == bottom,
+                left == right,
+                left == bottom,
+                right == bottom,
+            ];
+            if pairs.iter().filter(|is_equal| **is_equal).count() >= 3 {
+                return center;
+            }
+            match (x % 2, y % 2) {
+                (0, 0) if left == top => top,
+                (1, 0) if top == right => right,
+                (0, 1) if bottom == left => left,
+                (1, 1) if right == bottom => bottom,
+                _ => center,
+            }
+        })
+    }
+
+    pub fn upscaled(&self, upscale: Upscale) -> SharedImage {
+        match upscale {
+            Upscale::None => self.crop(0, 0, self.width(), self.height()),
+            Upscale::Scale2x => self.scale2x(),
+            Upscale::Scale3x => self.scale3x(),
+            Upscale::Epx => self.epx(),
+        }
+    }
+
+    fn transformed(
+        &self,
+        width: u32,
+        height: u32,
+        pixel: impl Fn(i32, i32) -> Color,
+    ) -> SharedImage {
+        let image = Self::new(width, height);
+        {
+            let mut image = image.lock();
+            image.palette = self.palette;
+            for y in 0..height {
+                for x in 0..width {
+                    let color = pixel(x as i32, y as i32);
+                    image.canvas.data[(y * width + x) as usize] = color;
+                }
+            }
+        }
+        image
+    }
+
+    fn clamped_pixel(&self, x: i32, y: i32) -> Color {
+        let x = x.clamp(0, self.width() as i32 - 1);
+        let y = y.clamp(0, self.height() as i32 - 1);
+        self.canvas.read_data(x as usize, y as usize)
+    }
+
+    fn neighbors(&self, x: i32, y: i32) -> (Color, Color, Color, Color, Color) {
+        (
+            self.clamped_pixel(x, y - 1),
+            self.clamped_pixel(x - 1, y),
+            self.clamped_pixel(x, y),
+            self.clamped_pixel(x + 1, y),
+            self.clamped_pixel(x, y + 1),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image_data(image: &SharedImage) -> Vec<Color> {
+        image.lock().canvas.data.clone()
+    }
+
+    #[test]
+    fn test_flip_rotate_crop() {
+        let image = Image::new(3, 2);
+        image.lock().canvas.data = vec![1, 2, 3, 4, 5, 6];
+        let image = image.lock();
+
+        assert_eq!(image_data(&image.flipped_h()), vec![3, 2, 1, 6, 5, 4]);
+        assert_eq!(image_data(&image.flipped_v()), vec![4, 5, 6, 1, 2, 3]);
+        assert_eq!(image_data(&image.rotate90()), vec![4, 1, 5, 2, 6, 3]);
+        assert_eq!(image_data(&image.crop(1, 0, 2, 2)), vec![2, 3, 5, 6]);
+        assert_eq!(image_data(&image.crop(2, 1, 2, 1)), vec![6, 0]);
+        assert_eq!(
+            image_data(&image.resize(6, 2)),
+            vec![1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6]
+        );
+    }
+
+    #[test]
+    fn test_upscalers() {
+        let image = Image::new(2, 2);
+        image.lock().canvas.data = vec![1, 0, 0, 1];
+        let image = image.lock();
+
+        let scaled = image.scale2x();
+        assert_eq!(scaled.lock().width(), 4);
+        assert_eq!(
+            image_data(&scaled),
+            vec![1, 1, 0, 0, 1, 0, 1, 0, 0, 1, 0, 1, 0, 0, 1, 1]
+        );
+        assert_eq!(image.scale3x().lock().width(), 6);
+        assert_eq!(image_data(&image.epx()).len(), 16);
+    }
+}
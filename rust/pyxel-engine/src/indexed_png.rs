@@ -0,0 +1,115 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Cursor};
+
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+
+use crate::image::{Color, Rgb24};
+
+pub struct IndexedPng {
+    pub width: u32,
+    pub height: u32,
+    pub palette: Vec<Rgb24>,
+    pub transparent: Option<Color>,
+    pub data: Vec<Color>,
+}
+
+impl IndexedPng {
+    pub fn load(filename: &str) -> Option<Self> {
+        let file_data = fs::read(filename).ok()?;
+        let mut decoder = Decoder::new(Cursor::new(file_data));
+        decoder.set_transformations(Transformations::IDENTITY);
+        let mut reader = decoder.read_info().ok()?;
+
+        let info = reader.info();
+        if info.color_type != ColorType::Indexed {
+            return None;
+        }
+        let bit_depth = info.bit_depth as usize;
+        let palette: Vec<Rgb24> = info
+            .palette
+            .as_ref()?
+            .chunks_exact(3)
+            .map(|rgb| ((rgb[0] as Rgb24) << 16) | ((rgb[1] as Rgb24) << 8) | rgb[2] as Rgb24)
+            .collect();
+        let transparent = info
+            .trns
+            .as_ref()
+            .and_then(|trns| trns.iter().position(|alpha| *alpha == 0))
+            .map(|index| index as Color);
+
+        let mut buf = vec![0; reader.output_buffer_size()?];
+        let output_info = reader.next_frame(&mut buf).ok()?;
+        let (width, height) = (output_info.width, output_info.height);
+
+        // Pixels narrower than a byte are packed from the most significant bit
+        let pixels_per_byte = 8 / bit_depth;
+        let mask = ((1_u16 << bit_depth) - 1) as u8;
+        let mut data = Vec::with_capacity((width * height) as usize);
+        for line in buf.chunks(output_info.line_size).take(height as usize) {
+            for x in 0..width as usize {
+                let byte = line[x / pixels_per_byte];
+                let shift = 8 - bit_depth * (x % pixels_per_byte + 1);
+                data.push((byte >> shift) & mask);
+            }
+        }
+
+        Some(Self {
+            width,
+            height,
+            palette,
+            transparent,
+            data,
+        })
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        let open_error = || format!("Failed to open file '{filename}'");
+        let file = File::create(filename).map_err(|_| open_error())?;
+        let mut encoder = Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(ColorType::Indexed);
+        encoder.set_depth(BitDepth::Eight);
+
+        let palette: Vec<u8> = self
+            .palette
+            .iter()
+            .flat_map(|rgb| [(rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8])
+            .collect();
+        encoder.set_palette(palette);
+        if let Some(transparent) = self.transparent {
+            let mut trns = vec![255; transparent as usize + 1];
+            trns[transparent as usize] = 0;
+            encoder.set_trns(trns);
+        }
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.data))
+            .map_err(|_| open_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load() {
+        let filename = std::env::temp_dir().join("pyxel_indexed_png_test.png");
+        let filename = filename.to_str().unwrap();
+        let png = IndexedPng {
+            width: 3,
+            height: 2,
+            palette: vec![0x000000, 0xff0000, 0x00ff00],
+            transparent: Some(1),
+            data: vec![0, 1, 2, 2, 1, 0],
+        };
+        png.save(filename).unwrap();
+
+        let loaded = IndexedPng::load(filename).unwrap();
+        fs::remove_file(filename).unwrap();
+        assert_eq!((loaded.width, loaded.height), (3, 2));
+        assert_eq!(loaded.palette, png.palette);
+        assert_eq!(loaded.transparent, Some(1));
+        assert_eq!(loaded.data, png.data);
+    }
+}
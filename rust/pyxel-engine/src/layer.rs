@@ -0,0 +1,105 @@
+use crate::canvas::StencilMode;
+use crate::image::{Color, Image, SharedImage};
+use crate::pyxel::Pyxel;
+
+pub struct Layer {
+    pub image: SharedImage,
+    pub visible: bool,
+    pub transparent: Option<Color>,
+}
+
+pub type SharedLayer = shared_type!(Layer);
+
+impl Layer {
+    pub fn new(width: u32, height: u32, transparent: Option<Color>) -> SharedLayer {
+        new_shared_type!(Self {
+            image: Image::new(width, height),
+            visible: true,
+            transparent,
+        })
+    }
+
+    // The camera offsets drawing into the layer so its content scrolls with it
+    pub fn camera(&mut self, x: f64, y: f64) {
+        self.image.lock().camera(x, y);
+    }
+
+    pub fn camera0(&mut self) {
+        self.image.lock().camera0();
+    }
+}
+
+impl Pyxel {
+    pub fn add_layer(&self, transparent: Option<Color>) -> SharedLayer {
+        let layer = Layer::new(self.width, self.height, transparent);
+        self.layers.lock().push(layer.clone());
+        layer
+    }
+
+    pub fn clear_layers(&self) {
+        self.layers.lock().clear();
+    }
+
+    pub(crate) fn composite_layers(&self) {
+        let layers = self.layers.lock();
+        if layers.is_empty() {
+            return;
+        }
+
+        let mut screen = self.screen.lock();
+        let clip_rect = screen.canvas.clip_rect;
+        let camera_x = screen.canvas.camera_x;
+        let camera_y = screen.canvas.camera_y;
+        let alpha = screen.canvas.alpha;
+        let stencil_mode = screen.canvas.stencil_mode;
+
+        screen.clip0();
+        screen.camera0();
+        screen.dither(1.0);
+        screen.canvas.stencil_mode = StencilMode::Off;
+
+        for layer in layers.iter() {
+            let layer = layer.lock();
+            if !layer.visible {
+                continue;
+            }
+
+            let image = layer.image.lock();
+            screen.canvas.blt(
+                0.0,
+                0.0,
+                &image.canvas,
+                0.0,
+                0.0,
+                image.width() as f64,
+                image.height() as f64,
+                layer.transparent,
+                None,
+            );
+        }
+
+        screen.canvas.clip_rect = clip_rect;
+        screen.canvas.camera_x = camera_x;
+        screen.canvas.camera_y = camera_y;
+        screen.dither(alpha);
+        screen.canvas.stencil_mode = stencil_mode;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_scrolls_content() {
+        let layer = Layer::new(16, 16, None);
+        let mut layer = layer.lock();
+        layer.camera(4.0, 2.0);
+        layer.image.lock().pset(6.0, 3.0, 5);
+        assert_eq!(layer.image.lock().canvas.read_data(2, 1), 5);
+
+        layer.camera0();
+        layer.image.lock().pset(6.0, 3.0, 7);
+        assert_eq!(layer.image.lock().canvas.read_data(6, 3), 7);
+    }
+}
@@ -0,0 +1,493 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::pyxel::IMAGES;
+use crate::settings::{NUM_IMAGES, TILE_SIZE};
+use crate::tilemap::{ImageSource, ImageTileCoord, SharedTilemap, Tilemap};
+
+#[derive(Debug, Deserialize)]
+struct LdtkTilesetDef {
+    uid: i64,
+    #[serde(rename = "relPath")]
+    rel_path: Option<String>,
+    #[serde(rename = "tileGridSize")]
+    tile_grid_size: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    padding: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct LdtkDefs {
+    #[serde(default)]
+    tilesets: Vec<LdtkTilesetDef>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LdtkTileInstance {
+    px: [i32; 2],
+    src: [i32; 2],
+    #[serde(default)]
+    f: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct LdtkFieldInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__value")]
+    value: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct LdtkEntityInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__grid")]
+    grid: [i32; 2],
+    px: [i32; 2],
+    width: u32,
+    height: u32,
+    #[serde(rename = "fieldInstances", default)]
+    field_instances: Vec<LdtkFieldInstance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LdtkLayerInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    layer_type: String,
+    #[serde(rename = "__cWid")]
+    columns: u32,
+    #[serde(rename = "__cHei")]
+    rows: u32,
+    #[serde(rename = "__gridSize")]
+    grid_size: u32,
+    #[serde(rename = "__tilesetDefUid")]
+    tileset_def_uid: Option<i64>,
+    #[serde(rename = "intGridCsv", default)]
+    int_grid_csv: Vec<u32>,
+    #[serde(rename = "gridTiles", default)]
+    grid_tiles: Vec<LdtkTileInstance>,
+    #[serde(rename = "autoLayerTiles", default)]
+    auto_layer_tiles: Vec<LdtkTileInstance>,
+    #[serde(rename = "entityInstances", default)]
+    entity_instances: Vec<LdtkEntityInstance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LdtkLevelData {
+    identifier: String,
+    #[serde(rename = "worldX", default)]
+    world_x: i32,
+    #[serde(rename = "worldY", default)]
+    world_y: i32,
+    #[serde(rename = "pxWid")]
+    px_wid: u32,
+    #[serde(rename = "pxHei")]
+    px_hei: u32,
+    #[serde(rename = "layerInstances")]
+    layer_instances: Option<Vec<LdtkLayerInstance>>,
+    #[serde(rename = "fieldInstances", default)]
+    field_instances: Vec<LdtkFieldInstance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LdtkProjectFile {
+    defs: LdtkDefs,
+    #[serde(default)]
+    levels: Vec<LdtkLevelData>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum LdtkValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Point(i32, i32),
+    Array(Vec<LdtkValue>),
+}
+
+pub struct LdtkEntity {
+    pub identifier: String,
+    pub x: i32,
+    pub y: i32,
+    pub grid_x: i32,
+    pub grid_y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub fields: BTreeMap<String, LdtkValue>,
+}
+
+pub struct LdtkLayer {
+    pub identifier: String,
+    pub tilemap: SharedTilemap,
+    pub int_grid: Vec<u32>,
+}
+
+pub struct LdtkLevel {
+    pub identifier: String,
+    pub world_x: i32,
+    pub world_y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub layers: Vec<LdtkLayer>,
+    pub entities: Vec<LdtkEntity>,
+    pub fields: BTreeMap<String, LdtkValue>,
+}
+
+pub struct LdtkProject {
+    pub levels: Vec<LdtkLevel>,
+    pub tileset_banks: BTreeMap<i64, u32>,
+}
+
+impl LdtkValue {
+    fn from_json(value: &Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Bool(value) => Self::Bool(*value),
+            Value::Number(number) => number
+                .as_i64()
+                .map_or_else(|| Self::Float(number.as_f64().unwrap_or(0.0)), Self::Int),
+            Value::String(value) => Self::String(value.clone()),
+            Value::Array(values) => Self::Array(values.iter().map(Self::from_json).collect()),
+            Value::Object(object) => {
+                // Points are the only object values that carry grid coordinates
+                let coord = |key: &str| object.get(key).and_then(Value::as_i64);
+                match (coord("cx"), coord("cy")) {
+                    (Some(x), Some(y)) => Self::Point(x as i32, y as i32),
+                    _ => Self::Null,
+                }
+            }
+        }
+    }
+}
+
+impl LdtkEntity {
+    pub fn field(&self, name: &str) -> Option<&LdtkValue> {
+        self.fields.get(name)
+    }
+}
+
+impl LdtkLevel {
+    pub fn layer(&self, identifier: &str) -> Option<&LdtkLayer> {
+        self.layers
+            .iter()
+            .find(|layer| layer.identifier == identifier)
+    }
+
+    pub fn entities(&self, identifier: &str) -> Vec<&LdtkEntity> {
+        self.entities
+            .iter()
+            .filter(|entity| entity.identifier == identifier)
+            .collect()
+    }
+}
+
+impl LdtkProject {
+    pub fn load(filename: &str, first_bank: Option<u32>) -> Result<Self, String> {
+        let json_text = fs::read_to_string(filename)
+            .map_err(|_| format!("Failed to open file '{filename}'"))?;
+        let base_dir = Path::new(filename).parent().unwrap_or(Path::new(""));
+        Self::parse(&json_text, base_dir, first_bank)
+    }
+
+    pub fn level(&self, identifier: &str) -> Option<&LdtkLevel> {
+        self.levels
+            .iter()
+            .find(|level| level.identifier == identifier)
+    }
+
+    fn parse(json_text: &str, base_dir: &Path, first_bank: Option<u32>) -> Result<Self, String> {
+        let project: LdtkProjectFile =
+            serde_json::from_str(json_text).map_err(|_| "Failed to parse LDtk file")?;
+
+        // Tilesets are assigned to consecutive image banks in definition order
+        if first_bank.is_none() && project.defs.tilesets.len() > 1 {
+            return Err("LDtk file with multiple tilesets needs a first image bank".to_string());
+        }
+        let first_bank = first_bank.unwrap_or(0);
+        let mut tileset_banks = BTreeMap::new();
+        for (i, tileset) in project.defs.tilesets.iter().enumerate() {
+            if tileset.tile_grid_size != TILE_SIZE {
+                return Err(format!(
+                    "LDtk tileset's grid size is not {TILE_SIZE}x{TILE_SIZE}"
+                ));
+            }
+            if tileset.spacing != 0 || tileset.padding != 0 {
+                return Err("LDtk tileset with spacing or padding is not supported".to_string());
+            }
+
+            let bank = first_bank + i as u32;
+            if bank >= NUM_IMAGES {
+                return Err("LDtk tilesets do not fit in the image banks".to_string());
+            }
+            if let Some(rel_path) = &tileset.rel_path {
+                let path = base_dir.join(rel_path);
+                if !path.is_file() {
+                    return Err(format!("Failed to open file '{}'", path.display()));
+                }
+                IMAGES.lock()[bank as usize].lock().load(
+                    0,
+                    0,
+                    &path.to_string_lossy(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                );
+            }
+            tileset_banks.insert(tileset.uid, bank);
+        }
+
+        let mut levels = Vec::new();
+        for level in project.levels {
+            let Some(layer_instances) = level.layer_instances else {
+                return Err(format!(
+                    "Level '{}' is saved in a separate file",
+                    level.identifier
+                ));
+            };
+
+            let mut layers = Vec::new();
+            let mut entities = Vec::new();
+            // LDtk lists layers from top to bottom, so they are reversed for drawing order
+            for layer in layer_instances.into_iter().rev() {
+                if layer.layer_type == "Entities" {
+                    entities.extend(layer.entity_instances.into_iter().map(Self::parse_entity));
+                    continue;
+                }
+                if layer.grid_size != TILE_SIZE {
+                    return Err(format!(
+                        "LDtk layer's grid size is not {TILE_SIZE}x{TILE_SIZE}"
+                    ));
+                }
+                let bank = layer
+                    .tileset_def_uid
+                    .and_then(|uid| tileset_banks.get(&uid).copied())
+                    .unwrap_or(0);
+                layers.push(Self::parse_layer(layer, bank)?);
+            }
+
+            levels.push(LdtkLevel {
+                identifier: level.identifier,
+                world_x: level.world_x,
+                world_y: level.world_y,
+                width: level.px_wid,
+                height: level.px_hei,
+                layers,
+                entities,
+                fields: Self::parse_fields(&level.field_instances),
+            });
+        }
+
+        Ok(Self {
+            levels,
+            tileset_banks,
+        })
+    }
+
+    fn parse_layer(layer: LdtkLayerInstance, bank: u32) -> Result<LdtkLayer, String> {
+        let (image_width, image_height) = {
+            let image = IMAGES.lock()[bank as usize].clone();
+            let image = image.lock();
+            (image.width() as i32, image.height() as i32)
+        };
+        let tilemap = Tilemap::new(layer.columns, layer.rows, ImageSource::Index(bank));
+        {
+            let mut tilemap = tilemap.lock();
+            let tiles = if layer.grid_tiles.is_empty() {
+                &layer.auto_layer_tiles
+            } else {
+                &layer.grid_tiles
+            };
+
+            if tiles.is_empty() {
+                // IntGrid values without an auto-layer become tiles on the first row
+                for (i, value) in layer.int_grid_csv.iter().enumerate() {
+                    let x = i as u32 % layer.columns;
+                    let y = i as u32 / layer.columns;
+                    let value = ImageTileCoord::try_from(*value).map_err(|_| {
+                        format!(
+                            "IntGrid value {value} in LDtk layer '{}' exceeds 255",
+                            layer.identifier
+                        )
+                    })?;
+                    tilemap
+                        .canvas
+                        .write_data(x as usize, y as usize, (value, 0));
+                }
+            }
+            if tiles.iter().any(|tile| tile.f != 0) {
+                println!(
+                    "Flipped tiles in LDtk layer '{}' are drawn without flipping",
+                    layer.identifier
+                );
+            }
+            for tile in tiles {
+                let x = tile.px[0] / TILE_SIZE as i32;
+                let y = tile.px[1] / TILE_SIZE as i32;
+                if x < 0 || y < 0 || x >= layer.columns as i32 || y >= layer.rows as i32 {
+                    continue;
+                }
+
+                // Tile sources must lie within the tileset image
+                let [src_x, src_y] = tile.src;
+                let tile_size = TILE_SIZE as i32;
+                let image_tile = match (
+                    ImageTileCoord::try_from(src_x / tile_size),
+                    ImageTileCoord::try_from(src_y / tile_size),
+                ) {
+                    (Ok(u), Ok(v))
+                        if src_x >= 0
+                            && src_y >= 0
+                            && src_x + tile_size <= image_width
+                            && src_y + tile_size <= image_height =>
+                    {
+                        (u, v)
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Tile source ({src_x}, {src_y}) in LDtk layer '{}' is outside the tileset image",
+                            layer.identifier
+                        ));
+                    }
+                };
+                tilemap
+                    .canvas
+                    .write_data(x as usize, y as usize, image_tile);
+            }
+        }
+
+        Ok(LdtkLayer {
+            identifier: layer.identifier,
+            tilemap,
+            int_grid: layer.int_grid_csv,
+        })
+    }
+
+    fn parse_entity(entity: LdtkEntityInstance) -> LdtkEntity {
+        LdtkEntity {
+            identifier: entity.identifier,
+            x: entity.px[0],
+            y: entity.px[1],
+            grid_x: entity.grid[0],
+            grid_y: entity.grid[1],
+            width: entity.width,
+            height: entity.height,
+            fields: Self::parse_fields(&entity.field_instances),
+        }
+    }
+
+    fn parse_fields(field_instances: &[LdtkFieldInstance]) -> BTreeMap<String, LdtkValue> {
+        field_instances
+            .iter()
+            .map(|field| (field.identifier.clone(), LdtkValue::from_json(&field.value)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT_JSON: &str = r#"{
+        "defs": { "tilesets": [{ "uid": 7, "relPath": null, "tileGridSize": 8 }] },
+        "levels": [{
+            "identifier": "Level_0", "worldX": 0, "worldY": 0, "pxWid": 24, "pxHei": 16,
+            "fieldInstances": [{ "__identifier": "music", "__value": 3 }],
+            "layerInstances": [
+                {
+                    "__identifier": "Entities", "__type": "Entities", "__cWid": 3, "__cHei": 2,
+                    "__gridSize": 8, "__tilesetDefUid": null,
+                    "entityInstances": [{
+                        "__identifier": "Player", "__grid": [1, 1], "px": [8, 8],
+                        "width": 8, "height": 8,
+                        "fieldInstances": [
+                            { "__identifier": "hp", "__value": 5 },
+                            { "__identifier": "name", "__value": "hero" },
+                            { "__identifier": "target", "__value": { "cx": 2, "cy": 0 } }
+                        ]
+                    }]
+                },
+                {
+                    "__identifier": "Tiles", "__type": "Tiles", "__cWid": 3, "__cHei": 2,
+                    "__gridSize": 8, "__tilesetDefUid": 7,
+                    "gridTiles": [{ "px": [16, 8], "src": [32, 8], "f": 0, "t": 0 }]
+                },
+                {
+                    "__identifier": "Collisions", "__type": "IntGrid", "__cWid": 3, "__cHei": 2,
+                    "__gridSize": 8, "__tilesetDefUid": null,
+                    "intGridCsv": [0, 1, 0, 1, 1, 2]
+                }
+            ]
+        }]
+    }"#;
+
+    #[test]
+    fn test_parse_project() {
+        let project = LdtkProject::parse(PROJECT_JSON, Path::new(""), None).unwrap();
+        let level = project.level("Level_0").unwrap();
+        assert_eq!((level.width, level.height), (24, 16));
+        assert_eq!(level.fields["music"], LdtkValue::Int(3));
+
+        let layer_names: Vec<&str> = level
+            .layers
+            .iter()
+            .map(|layer| layer.identifier.as_str())
+            .collect();
+        assert_eq!(layer_names, vec!["Collisions", "Tiles"]);
+
+        let collisions = level.layer("Collisions").unwrap();
+        assert_eq!(collisions.int_grid, vec![0, 1, 0, 1, 1, 2]);
+        assert_eq!(collisions.tilemap.lock().pget(2.0, 1.0), (2, 0));
+        assert_eq!(
+            level.layer("Tiles").unwrap().tilemap.lock().pget(2.0, 1.0),
+            (4, 1)
+        );
+
+        let players = level.entities("Player");
+        assert_eq!(players.len(), 1);
+        assert_eq!((players[0].grid_x, players[0].grid_y), (1, 1));
+        assert_eq!(players[0].field("hp"), Some(&LdtkValue::Int(5)));
+        assert_eq!(
+            players[0].field("name"),
+            Some(&LdtkValue::String("hero".to_string()))
+        );
+        assert_eq!(players[0].field("target"), Some(&LdtkValue::Point(2, 0)));
+    }
+
+    #[test]
+    fn test_unsupported_tilesets() {
+        let two_tilesets = r#"{ "defs": { "tilesets": [
+            { "uid": 1, "relPath": null, "tileGridSize": 8 },
+            { "uid": 2, "relPath": null, "tileGridSize": 8 }
+        ] } }"#;
+        assert!(LdtkProject::parse(two_tilesets, Path::new(""), None).is_err());
+
+        let spacing = r#"{ "defs": { "tilesets": [
+            { "uid": 1, "relPath": null, "tileGridSize": 8, "spacing": 1, "padding": 0 }
+        ] } }"#;
+        assert!(LdtkProject::parse(spacing, Path::new(""), None).is_err());
+
+        let missing_image = r#"{ "defs": { "tilesets": [
+            { "uid": 1, "relPath": "missing.png", "tileGridSize": 8 }
+        ] } }"#;
+        assert!(LdtkProject::parse(missing_image, Path::new(""), None).is_err());
+
+        let large_value = PROJECT_JSON.replace("[0, 1, 0, 1, 1, 2]", "[0, 1, 0, 1, 1, 256]");
+        assert!(LdtkProject::parse(&large_value, Path::new(""), None).is_err());
+
+        let large_src = PROJECT_JSON.replace("[32, 8]", "[4096, 8]");
+        assert!(LdtkProject::parse(&large_src, Path::new(""), None).is_err());
+    }
+}
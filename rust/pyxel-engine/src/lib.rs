@@ -7,6 +7,7 @@
     clippy::cast_sign_loss,
     clippy::float_cmp,
     clippy::fn_params_excessive_bools,
+    clippy::missing_errors_doc,
     clippy::missing_panics_doc,
     clippy::module_name_repetitions,
     clippy::must_use_candidate,
@@ -22,45 +23,79 @@
 
 #[macro_use]
 mod utils;
+mod aseprite_parser;
+mod atlas;
 mod audio;
+mod autotile;
 mod blip_buf;
+mod camera;
 mod canvas;
 mod channel;
+mod chunked_tilemap;
 mod font;
 mod graphics;
 mod image;
+mod image_transform;
+mod indexed_png;
 mod input;
+mod layer;
+mod ldtk_parser;
 mod math;
 mod mml_parser;
 mod music;
 mod old_resource_data;
 mod oscillator;
+mod particle;
+mod pathfinding;
 mod profiler;
 mod pyxel;
+mod quantizer;
 mod rect_area;
 mod resource;
 mod resource_data;
+mod rich_text;
 mod screencast;
 mod settings;
 mod sound;
+mod sprite;
 mod system;
+mod text_layout;
+mod tile_animation;
+mod tile_collision;
 mod tilemap;
 mod tmx_parser;
+mod tmx_writer;
 mod tone;
 //mod voice;
 mod watch_info;
 
 use pyxel_platform::keys;
 
+pub use crate::aseprite_parser::{AsepriteData, AsepriteDirection, AsepriteFrame, AsepriteTag};
+pub use crate::atlas::{Atlas, AtlasEntry};
+pub use crate::autotile::{AutotileLayout, Terrain};
+pub use crate::camera::Camera;
+pub use crate::canvas::StencilMode;
 pub use crate::channel::{Channel, Detune, Note, SharedChannel, Speed, Volume};
+pub use crate::chunked_tilemap::{ChunkCoord, ChunkedTilemap, SharedChunkedTilemap};
 pub use crate::font::{Font, SharedFont};
 pub use crate::image::{Color, Image, Rgb24, SharedImage};
+pub use crate::image_transform::Upscale;
 pub use crate::keys::*;
+pub use crate::layer::{Layer, SharedLayer};
+pub use crate::ldtk_parser::{LdtkEntity, LdtkLayer, LdtkLevel, LdtkProject, LdtkValue};
 pub use crate::music::{Music, SharedMusic, SharedSeq};
 pub use crate::oscillator::{Effect, Gain, ToneIndex};
+pub use crate::particle::{Emitter, Particle, ParticleShape, SharedEmitter};
+pub use crate::pathfinding::{DistanceField, FlowField, PathRules};
 pub use crate::pyxel::{init, Pyxel};
+pub use crate::quantizer::{Dither, Quantize};
 pub use crate::settings::*;
 pub use crate::sound::{SharedSound, Sound};
+pub use crate::sprite::{Animation, AnimationFrame, LoopMode, Sprite};
 pub use crate::system::PyxelCallback;
+pub use crate::text_layout::TextAlign;
+pub use crate::tile_animation::TileAnimation;
+pub use crate::tile_collision::{RaycastHit, SweepResult};
 pub use crate::tilemap::{ImageSource, ImageTileCoord, SharedTilemap, Tile, Tilemap};
 pub use crate::tone::{Amp4, Noise, SharedTone, Tone, Waveform};
@@ -1,4 +1,5 @@
 use std::f64::consts::PI;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{LazyLock, Mutex};
 
 use noise::{NoiseFn, Perlin};
@@ -12,11 +13,20 @@ static RNG: LazyLock<Mutex<Xoshiro256StarStar>> = LazyLock::new(|| {
     Mutex::new(Xoshiro256StarStar::seed_from_u64(seed as u64))
 });
 
+static RNG_COUNT: AtomicU64 = AtomicU64::new(0);
+
 static PERLIN: LazyLock<Mutex<Perlin>> = LazyLock::new(|| {
     let seed = pyxel_platform::elapsed_time();
     Mutex::new(Perlin::new(seed))
 });
 
+// RNGs owned by objects get distinct seeds even when created in the same millisecond
+pub(crate) fn new_rng() -> Xoshiro256StarStar {
+    let count = RNG_COUNT.fetch_add(1, Ordering::Relaxed);
+    let seed = (count << 32) | pyxel_platform::elapsed_time() as u64;
+    Xoshiro256StarStar::seed_from_u64(seed)
+}
+
 impl Pyxel {
     pub fn ceil(x: f64) -> i32 {
         f64::ceil(x) as i32
@@ -0,0 +1,304 @@
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256StarStar;
+
+use crate::image::{Color, Image};
+use crate::math::new_rng;
+use crate::pyxel::{Pyxel, IMAGES};
+use crate::settings::NUM_IMAGES;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ParticleShape {
+    Pixel,
+    Circle(f64),
+    Sprite(u32, f64, f64, f64, f64),
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Particle {
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+    pub age: u32,
+    pub lifetime: u32,
+}
+
+pub struct Emitter {
+    pub x: f64,
+    pub y: f64,
+    pub active: bool,
+    pub spawn_rate: f64,
+    pub max_particles: usize,
+    pub lifetime: (u32, u32),
+    pub speed: (f64, f64),
+    pub angle: (f64, f64),
+    pub gravity: (f64, f64),
+    pub drag: f64,
+    pub colors: Vec<Color>,
+    pub color_key: Option<Color>,
+    pub particles: Vec<Particle>,
+    shape: ParticleShape,
+    spawn_budget: f64,
+    rng: Xoshiro256StarStar,
+}
+
+pub type SharedEmitter = shared_type!(Emitter);
+
+impl Particle {
+    pub fn progress(&self) -> f64 {
+        self.age as f64 / self.lifetime.max(1) as f64
+    }
+}
+
+impl Emitter {
+    pub fn new(x: f64, y: f64) -> SharedEmitter {
+        new_shared_type!(Self {
+            x,
+            y,
+            active: true,
+            spawn_rate: 1.0,
+            max_particles: 256,
+            lifetime: (30, 30),
+            speed: (0.5, 1.0),
+            angle: (0.0, 360.0),
+            gravity: (0.0, 0.0),
+            drag: 0.0,
+            colors: vec![7],
+            color_key: None,
+            particles: Vec::new(),
+            shape: ParticleShape::Pixel,
+            spawn_budget: 0.0,
+            rng: new_rng(),
+        })
+    }
+
+    pub fn shape(&self) -> ParticleShape {
+        self.shape
+    }
+
+    pub fn set_shape(&mut self, shape: ParticleShape) -> Result<(), String> {
+        if let ParticleShape::Sprite(img, ..) = shape {
+            if img >= NUM_IMAGES {
+                return Err(format!("Image index {img} is out of range"));
+            }
+        }
+        self.shape = shape;
+        Ok(())
+    }
+
+    // Each emitter has its own RNG so as not to disturb the sequence of rndi/rndf
+    pub fn seed(&mut self, seed: u32) {
+        self.rng = Xoshiro256StarStar::seed_from_u64(seed as u64);
+    }
+
+    pub fn burst(&mut self, count: u32) {
+        for _ in 0..count {
+            if self.particles.len() >= self.max_particles {
+                break;
+            }
+            let speed = self.random_range(self.speed);
+            let angle = self.random_range(self.angle);
+            let (min_lifetime, max_lifetime) = self.lifetime;
+            let lifetime = self
+                .rng
+                .random_range(min_lifetime.min(max_lifetime)..=min_lifetime.max(max_lifetime))
+                .max(1);
+            self.particles.push(Particle {
+                x: self.x,
+                y: self.y,
+                vx: speed * Pyxel::cos(angle),
+                vy: speed * Pyxel::sin(angle),
+                age: 0,
+                lifetime,
+            });
+        }
+    }
+
+    pub fn update(&mut self) {
+        let (gravity_x, gravity_y) = self.gravity;
+        let damping = 1.0 - self.drag.clamp(0.0, 1.0);
+        self.particles.retain_mut(|particle| {
+            particle.age += 1;
+            particle.vx = (particle.vx + gravity_x) * damping;
+            particle.vy = (particle.vy + gravity_y) * damping;
+            particle.x += particle.vx;
+            particle.y += particle.vy;
+            particle.age < particle.lifetime
+        });
+
+        // Fractional spawn rates carry over so that slow emitters still fire
+        if self.active {
+            self.spawn_budget += self.spawn_rate.max(0.0);
+            let count = self.spawn_budget.floor();
+            self.spawn_budget -= count;
+            self.burst(count as u32);
+        } else {
+            self.spawn_budget = 0.0;
+        }
+    }
+
+    pub fn color(&self, particle: &Particle) -> Color {
+        if self.colors.is_empty() {
+            return 0;
+        }
+        let index = (particle.progress() * self.colors.len() as f64) as usize;
+        self.colors[index.min(self.colors.len() - 1)]
+    }
+
+    pub fn draw(&self, image: &mut Image) {
+        for particle in &self.particles {
+            let color = self.color(particle);
+            match self.shape {
+                ParticleShape::Pixel => image.pset(particle.x, particle.y, color),
+                ParticleShape::Circle(radius) => {
+                    // Circles shrink towards the end of their lifetime
+                    let radius = radius * (1.0 - particle.progress());
+                    image.circ(particle.x, particle.y, radius, color);
+                }
+                ParticleShape::Sprite(img, u, v, w, h) => {
+                    let sprite_image = IMAGES.lock()[img as usize].clone();
+                    image.blt(
+                        particle.x - w / 2.0,
+                        particle.y - h / 2.0,
+                        sprite_image,
+                        u,
+                        v,
+                        w,
+                        h,
+                        self.color_key,
+                        None,
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.spawn_budget = 0.0;
+    }
+
+    fn random_range(&mut self, (a, b): (f64, f64)) -> f64 {
+        self.rng.random_range(a.min(b)..=a.max(b))
+    }
+}
+
+impl Pyxel {
+    pub fn add_emitter(&self, x: f64, y: f64) -> SharedEmitter {
+        let emitter = Emitter::new(x, y);
+        self.emitters.lock().push(emitter.clone());
+        emitter
+    }
+
+    pub fn clear_emitters(&self) {
+        self.emitters.lock().clear();
+    }
+
+    pub fn draw_emitters(&self) {
+        let mut screen = self.screen.lock();
+        for emitter in self.emitters.lock().iter() {
+            emitter.lock().draw(&mut screen);
+        }
+    }
+
+    pub(crate) fn update_emitters(&self) {
+        for emitter in self.emitters.lock().iter() {
+            emitter.lock().update();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_and_update() {
+        let emitter = Emitter::new(10.0, 10.0);
+        let mut emitter = emitter.lock();
+        emitter.spawn_rate = 0.5;
+        emitter.lifetime = (3, 3);
+        emitter.speed = (1.0, 1.0);
+        emitter.angle = (0.0, 0.0);
+        emitter.gravity = (0.0, 0.5);
+
+        emitter.update();
+        assert!(emitter.particles.is_empty());
+        emitter.update();
+        assert_eq!(emitter.particles.len(), 1);
+        assert_eq!(
+            (emitter.particles[0].x, emitter.particles[0].y),
+            (10.0, 10.0)
+        );
+
+        emitter.active = false;
+        emitter.update();
+        let particle = emitter.particles[0];
+        assert_eq!((particle.x, particle.y), (11.0, 10.5));
+
+        emitter.update();
+        emitter.update();
+        assert!(emitter.particles.is_empty());
+
+        emitter.max_particles = 4;
+        emitter.burst(10);
+        assert_eq!(emitter.particles.len(), 4);
+    }
+
+    #[test]
+    fn test_seed_and_shape() {
+        // Emitters created back to back have distinct RNG streams
+        let emitter1 = Emitter::new(0.0, 0.0);
+        let emitter2 = Emitter::new(0.0, 0.0);
+        emitter1.lock().burst(8);
+        emitter2.lock().burst(8);
+        assert_ne!(emitter1.lock().particles, emitter2.lock().particles);
+
+        let emitter1 = Emitter::new(0.0, 0.0);
+        let emitter2 = Emitter::new(0.0, 0.0);
+        let (mut emitter1, mut emitter2) = (emitter1.lock(), emitter2.lock());
+        for emitter in [&mut emitter1, &mut emitter2] {
+            emitter.lifetime = (10, 1);
+            emitter.seed(42);
+            emitter.burst(8);
+        }
+        assert_eq!(emitter1.particles, emitter2.particles);
+        assert!(emitter1
+            .particles
+            .iter()
+            .all(|particle| (1..=10).contains(&particle.lifetime)));
+
+        assert!(emitter1
+            .set_shape(ParticleShape::Sprite(NUM_IMAGES, 0.0, 0.0, 8.0, 8.0))
+            .is_err());
+        assert_eq!(emitter1.shape(), ParticleShape::Pixel);
+        assert!(emitter1.set_shape(ParticleShape::Circle(2.0)).is_ok());
+        assert_eq!(emitter1.shape(), ParticleShape::Circle(2.0));
+    }
+
+    #[test]
+    fn test_color_ramp() {
+        let emitter = Emitter::new(0.0, 0.0);
+        let mut emitter = emitter.lock();
+        emitter.colors = vec![10, 9, 8];
+        let mut particle = Particle {
+            x: 0.0,
+            y: 0.0,
+            vx: 0.0,
+            vy: 0.0,
+            age: 0,
+            lifetime: 6,
+        };
+        assert_eq!(emitter.color(&particle), 10);
+        particle.age = 3;
+        assert_eq!(emitter.color(&particle), 9);
+        particle.age = 5;
+        assert_eq!(emitter.color(&particle), 8);
+
+        let image = Image::new(16, 16);
+        emitter.particles.push(Particle { x: 4.0, ..particle });
+        emitter.draw(&mut image.lock());
+        assert_eq!(image.lock().pget(4.0, 0.0), 8);
+    }
+}
@@ -0,0 +1,393 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64::consts::SQRT_2;
+
+use crate::tilemap::Tilemap;
+
+const ORTHOGONAL_DIRS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const DIAGONAL_DIRS: [(i32, i32); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct PathRules {
+    pub diagonal: bool,
+    pub cut_corners: bool,
+}
+
+pub struct DistanceField {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<f64>,
+}
+
+pub struct FlowField {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<(i32, i32)>,
+}
+
+struct OpenNode {
+    priority: f64,
+    index: usize,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so that BinaryHeap pops the lowest priority first
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+impl DistanceField {
+    pub fn get(&self, x: i32, y: i32) -> f64 {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return f64::INFINITY;
+        }
+        self.data[(y * self.width as i32 + x) as usize]
+    }
+}
+
+impl FlowField {
+    pub fn get(&self, x: i32, y: i32) -> (i32, i32) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return (0, 0);
+        }
+        self.data[(y * self.width as i32 + x) as usize]
+    }
+}
+
+impl Tilemap {
+    pub fn find_path(
+        &self,
+        start: (i32, i32),
+        goal: (i32, i32),
+        cost: impl Fn(i32, i32) -> Option<f64>,
+        rules: PathRules,
+    ) -> Option<Vec<(i32, i32)>> {
+        if !self.in_grid(start.0, start.1)
+            || !self.in_grid(goal.0, goal.1)
+            || cost(goal.0, goal.1).is_none()
+        {
+            return None;
+        }
+
+        let width = self.width() as i32;
+        let to_index = |x: i32, y: i32| (y * width + x) as usize;
+        let heuristic = |x: i32, y: i32| {
+            let (dx, dy) = ((goal.0 - x).abs() as f64, (goal.1 - y).abs() as f64);
+            if rules.diagonal {
+                dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy)
+            } else {
+                dx + dy
+            }
+        };
+
+        let num_tiles = (self.width() * self.height()) as usize;
+        let mut costs = vec![f64::INFINITY; num_tiles];
+        let mut parents = vec![usize::MAX; num_tiles];
+        let mut open = BinaryHeap::new();
+        let start_index = to_index(start.0, start.1);
+        costs[start_index] = 0.0;
+        open.push(OpenNode {
+            priority: heuristic(start.0, start.1),
+            index: start_index,
+        });
+
+        while let Some(OpenNode { priority, index }) = open.pop() {
+            let (x, y) = (index as i32 % width, index as i32 / width);
+            if (x, y) == goal {
+                let mut path = vec![goal];
+                let mut index = index;
+                while parents[index] != usize::MAX {
+                    index = parents[index];
+                    path.push((index as i32 % width, index as i32 / width));
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if priority > costs[index] + heuristic(x, y) {
+                continue;
+            }
+
+            for (nx, ny, step_cost) in self.neighbors(x, y, &cost, rules) {
+                let neighbor_index = to_index(nx, ny);
+                let new_cost = costs[index] + step_cost;
+                if new_cost < costs[neighbor_index] {
+                    costs[neighbor_index] = new_cost;
+                    parents[neighbor_index] = index;
+                    open.push(OpenNode {
+                        priority: new_cost + heuristic(nx, ny),
+                        index: neighbor_index,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn find_path_by_attr(
+        &self,
+        start: (i32, i32),
+        goal: (i32, i32),
+        mask: u32,
+        rules: PathRules,
+    ) -> Option<Vec<(i32, i32)>> {
+        self.find_path(start, goal, self.attr_cost(mask), rules)
+    }
+
+    pub fn distance_field(
+        &self,
+        goals: &[(i32, i32)],
+        cost: impl Fn(i32, i32) -> Option<f64>,
+        rules: PathRules,
+    ) -> DistanceField {
+        let width = self.width() as i32;
+        let mut data = vec![f64::INFINITY; (self.width() * self.height()) as usize];
+        let mut open = BinaryHeap::new();
+        for &(x, y) in goals {
+            if self.in_grid(x, y) && cost(x, y).is_some() {
+                let index = (y * width + x) as usize;
+                data[index] = 0.0;
+                open.push(OpenNode {
+                    priority: 0.0,
+                    index,
+                });
+            }
+        }
+
+        // Dijkstra expands outwards from every goal at once
+        while let Some(OpenNode { priority, index }) = open.pop() {
+            if priority > data[index] {
+                continue;
+            }
+            let (x, y) = (index as i32 % width, index as i32 / width);
+
+            // Costs are paid when entering a tile, so the walk is reversed here
+            let tile_cost = cost(x, y).unwrap_or(0.0);
+            for (nx, ny, _) in self.neighbors(x, y, &cost, rules) {
+                let step_cost = if nx != x && ny != y {
+                    tile_cost * SQRT_2
+                } else {
+                    tile_cost
+                };
+                let neighbor_index = (ny * width + nx) as usize;
+                let new_cost = priority + step_cost;
+                if new_cost < data[neighbor_index] {
+                    data[neighbor_index] = new_cost;
+                    open.push(OpenNode {
+                        priority: new_cost,
+                        index: neighbor_index,
+                    });
+                }
+            }
+        }
+
+        DistanceField {
+            width: self.width(),
+            height: self.height(),
+            data,
+        }
+    }
+
+    pub fn distance_field_by_attr(
+        &self,
+        goals: &[(i32, i32)],
+        mask: u32,
+        rules: PathRules,
+    ) -> DistanceField {
+        self.distance_field(goals, self.attr_cost(mask), rules)
+    }
+
+    pub fn flow_field(
+        &self,
+        goals: &[(i32, i32)],
+        cost: impl Fn(i32, i32) -> Option<f64>,
+        rules: PathRules,
+    ) -> FlowField {
+        let distances = self.distance_field(goals, &cost, rules);
+        let mut data = vec![(0, 0); distances.data.len()];
+
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                let distance = distances.get(x, y);
+                if distance == 0.0 || distance.is_infinite() {
+                    continue;
+                }
+
+                // The cheapest move includes the cost of entering the neighbor
+                let mut best = f64::INFINITY;
+                for (nx, ny, step_cost) in self.neighbors(x, y, &cost, rules) {
+                    let distance = step_cost + distances.get(nx, ny);
+                    if distance < best {
+                        best = distance;
+                        data[(y * self.width() as i32 + x) as usize] = (nx - x, ny - y);
+                    }
+                }
+            }
+        }
+
+        FlowField {
+            width: self.width(),
+            height: self.height(),
+            data,
+        }
+    }
+
+    pub fn flow_field_by_attr(
+        &self,
+        goals: &[(i32, i32)],
+        mask: u32,
+        rules: PathRules,
+    ) -> FlowField {
+        self.flow_field(goals, self.attr_cost(mask), rules)
+    }
+
+    fn attr_cost(&self, mask: u32) -> impl Fn(i32, i32) -> Option<f64> + '_ {
+        move |x, y| (self.attr_at(x, y) & mask == 0).then_some(1.0)
+    }
+
+    fn in_grid(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width() as i32 && y < self.height() as i32
+    }
+
+    fn neighbors(
+        &self,
+        x: i32,
+        y: i32,
+        cost: &impl Fn(i32, i32) -> Option<f64>,
+        rules: PathRules,
+    ) -> Vec<(i32, i32, f64)> {
+        let passable = |x: i32, y: i32| self.in_grid(x, y) && cost(x, y).is_some();
+        let mut neighbors = Vec::with_capacity(8);
+
+        for (dx, dy) in ORTHOGONAL_DIRS {
+            let (nx, ny) = (x + dx, y + dy);
+            if let Some(step_cost) = self.in_grid(nx, ny).then(|| cost(nx, ny)).flatten() {
+                neighbors.push((nx, ny, step_cost));
+            }
+        }
+
+        if rules.diagonal {
+            for (dx, dy) in DIAGONAL_DIRS {
+                let (nx, ny) = (x + dx, y + dy);
+                let Some(step_cost) = self.in_grid(nx, ny).then(|| cost(nx, ny)).flatten() else {
+                    continue;
+                };
+
+                // Cutting a corner needs one open side, otherwise both sides must be open
+                let (side_x, side_y) = (passable(nx, y), passable(x, ny));
+                let is_allowed = if rules.cut_corners {
+                    side_x || side_y
+                } else {
+                    side_x && side_y
+                };
+                if is_allowed {
+                    neighbors.push((nx, ny, step_cost * SQRT_2));
+                }
+            }
+        }
+
+        neighbors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::{ImageSource, SharedTilemap};
+
+    const SOLID: u32 = 1;
+
+    fn maze_tilemap() -> SharedTilemap {
+        // A wall at x=2 with a single gap at the bottom row
+        let tilemap = Tilemap::new(5, 5, ImageSource::Index(0));
+        {
+            let mut tilemap = tilemap.lock();
+            tilemap.set_attr((1, 0), SOLID);
+            tilemap.rect(2.0, 0.0, 1.0, 4.0, (1, 0));
+        }
+        tilemap
+    }
+
+    #[test]
+    fn test_find_path() {
+        let tilemap = maze_tilemap();
+        let tilemap = tilemap.lock();
+
+        let path = tilemap
+            .find_path_by_attr((0, 0), (4, 0), SOLID, PathRules::default())
+            .unwrap();
+        assert_eq!(path.len(), 13);
+        assert_eq!(path[0], (0, 0));
+        assert_eq!(path[path.len() - 1], (4, 0));
+        assert!(path.contains(&(2, 4)));
+
+        let rules = PathRules {
+            diagonal: true,
+            cut_corners: false,
+        };
+        let path = tilemap
+            .find_path_by_attr((0, 0), (4, 0), SOLID, rules)
+            .unwrap();
+        assert_eq!(path.len(), 11);
+        assert!(path.contains(&(1, 4)) && path.contains(&(3, 4)));
+
+        let rules = PathRules {
+            diagonal: true,
+            cut_corners: true,
+        };
+        let path = tilemap
+            .find_path_by_attr((0, 0), (4, 0), SOLID, rules)
+            .unwrap();
+        assert_eq!(path.len(), 9);
+
+        assert!(tilemap
+            .find_path_by_attr((0, 0), (2, 0), SOLID, PathRules::default())
+            .is_none());
+    }
+
+    #[test]
+    fn test_distance_and_flow_fields() {
+        let tilemap = maze_tilemap();
+        let tilemap = tilemap.lock();
+
+        let distances = tilemap.distance_field_by_attr(&[(4, 0)], SOLID, PathRules::default());
+        assert_eq!(distances.get(4, 0), 0.0);
+        assert_eq!(distances.get(0, 0), 12.0);
+        assert!(distances.get(2, 0).is_infinite());
+
+        let flow = tilemap.flow_field_by_attr(&[(4, 0)], SOLID, PathRules::default());
+        assert_eq!(flow.get(0, 0), (1, 0));
+        assert_eq!(flow.get(2, 4), (1, 0));
+        assert_eq!(flow.get(4, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_weighted_flow_field() {
+        // Entering the tile between the start and the goal is expensive
+        let tilemap = Tilemap::new(3, 2, ImageSource::Index(0));
+        let tilemap = tilemap.lock();
+        let cost = |x, y| Some(if (x, y) == (1, 0) { 10.0 } else { 1.0 });
+
+        let distances = tilemap.distance_field(&[(2, 0)], cost, PathRules::default());
+        assert_eq!(distances.get(0, 0), 4.0);
+
+        let flow = tilemap.flow_field(&[(2, 0)], cost, PathRules::default());
+        assert_eq!(flow.get(0, 0), (0, 1));
+        assert_eq!(flow.get(1, 0), (1, 0));
+    }
+}
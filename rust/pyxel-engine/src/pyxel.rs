@@ -1,20 +1,26 @@
 use std::cmp::max;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::LazyLock;
 
+use crate::atlas::Atlas;
 use crate::audio::Audio;
+use crate::camera::Camera;
 use crate::channel::{Channel, SharedChannel};
 use crate::graphics::Graphics;
 use crate::image::{Image, Rgb24, SharedImage};
 use crate::input::Input;
 use crate::keys::Key;
+use crate::layer::SharedLayer;
 use crate::music::{Music, SharedMusic};
+use crate::particle::SharedEmitter;
 use crate::resource::Resource;
 use crate::settings::{
     CURSOR_DATA, CURSOR_HEIGHT, CURSOR_WIDTH, DEFAULT_COLORS, DEFAULT_FPS, DEFAULT_QUIT_KEY,
-    DEFAULT_TITLE, DEFAULT_TONES, DISPLAY_RATIO, FONT_DATA, FONT_HEIGHT, FONT_WIDTH, ICON_COLKEY,
-    ICON_DATA, ICON_SCALE, IMAGE_SIZE, NUM_CHANNELS, NUM_FONT_ROWS, NUM_IMAGES, NUM_MUSICS,
-    NUM_SOUNDS, NUM_TILEMAPS, NUM_TONES, TILEMAP_SIZE,
+    DEFAULT_TITLE, DEFAULT_TONES, DISPLAY_RATIO, FONT_DATA, FONT_EXT_DATA, FONT_HEIGHT, FONT_WIDTH,
+    ICON_COLKEY, ICON_DATA, ICON_SCALE, IMAGE_SIZE, MAX_FONT_CODE, MAX_FONT_GLYPHS, MIN_FONT_CODE,
+    NUM_CHANNELS, NUM_FONT_ROWS, NUM_IMAGES, NUM_MUSICS, NUM_SOUNDS, NUM_TILEMAPS, NUM_TONES,
+    TILEMAP_SIZE,
 };
 use crate::sound::{SharedSound, Sound};
 use crate::system::System;
@@ -23,6 +29,8 @@ use crate::tone::{SharedTone, Tone};
 
 static IS_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
+pub static FPS: AtomicU32 = AtomicU32::new(DEFAULT_FPS);
+
 pub static COLORS: LazyLock<shared_type!(Vec<Rgb24>)> =
     LazyLock::new(|| new_shared_type!(DEFAULT_COLORS.to_vec()));
 
@@ -46,11 +54,12 @@ static CURSOR_IMAGE: LazyLock<SharedImage> = LazyLock::new(|| {
 
 pub static FONT_IMAGE: LazyLock<SharedImage> = LazyLock::new(|| {
     let width = FONT_WIDTH * NUM_FONT_ROWS;
-    let height = FONT_HEIGHT * (FONT_DATA.len() as u32).div_ceil(NUM_FONT_ROWS);
+    let height = FONT_HEIGHT * MAX_FONT_GLYPHS.div_ceil(NUM_FONT_ROWS);
     let image = Image::new(width, height);
     {
         let mut image = image.lock();
-        for (fi, data) in FONT_DATA.iter().enumerate() {
+        let ext_data = FONT_EXT_DATA.iter().map(|(_, data)| data);
+        for (fi, data) in FONT_DATA.iter().chain(ext_data).enumerate() {
             let row = fi as u32 / NUM_FONT_ROWS;
             let col = fi as u32 % NUM_FONT_ROWS;
             let mut data = *data;
@@ -68,6 +77,16 @@ pub static FONT_IMAGE: LazyLock<SharedImage> = LazyLock::new(|| {
     image
 });
 
+pub static FONT_GLYPHS: LazyLock<shared_type!(HashMap<char, u32>)> = LazyLock::new(|| {
+    let ascii_codes = MIN_FONT_CODE..=MAX_FONT_CODE;
+    let ext_codes = FONT_EXT_DATA.iter().map(|(c, _)| *c);
+    new_shared_type!(ascii_codes
+        .chain(ext_codes)
+        .enumerate()
+        .map(|(index, c)| (c, index as u32))
+        .collect())
+});
+
 pub static CHANNELS: LazyLock<shared_type!(Vec<SharedChannel>)> =
     LazyLock::new(|| new_shared_type!((0..NUM_CHANNELS).map(|_| Channel::new()).collect()));
 
@@ -117,6 +136,10 @@ pub struct Pyxel {
     pub images: shared_type!(Vec<SharedImage>),
     pub tilemaps: shared_type!(Vec<SharedTilemap>),
     pub screen: SharedImage,
+    pub layers: shared_type!(Vec<SharedLayer>),
+    pub atlas: Atlas,
+    pub view_camera: Camera,
+    pub emitters: shared_type!(Vec<SharedEmitter>),
     pub cursor: SharedImage,
     pub font: SharedImage,
 
@@ -146,6 +169,7 @@ pub fn init(
     let title = title.unwrap_or(DEFAULT_TITLE);
     let quit_key = quit_key.unwrap_or(DEFAULT_QUIT_KEY);
     let fps = fps.unwrap_or(DEFAULT_FPS);
+    FPS.store(fps, Ordering::Relaxed);
 
     // Platform
     pyxel_platform::init(|display_width, display_height| {
@@ -186,6 +210,10 @@ pub fn init(
     let images = IMAGES.clone();
     let tilemaps = TILEMAPS.clone();
     let screen = Image::new(width, height);
+    let layers = new_shared_type!(Vec::new());
+    let atlas = Atlas::new();
+    let view_camera = Camera::new();
+    let emitters = new_shared_type!(Vec::new());
     let cursor = CURSOR_IMAGE.clone();
     let font = FONT_IMAGE.clone();
 
@@ -221,6 +249,10 @@ pub fn init(
         images,
         tilemaps,
         screen,
+        layers,
+        atlas,
+        view_camera,
+        emitters,
         cursor,
         font,
 
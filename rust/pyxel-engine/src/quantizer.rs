@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+
+use crate::canvas::DITHERING_MATRIX;
+use crate::image::{Color, Rgb24};
+use crate::settings::{DITHER_FLOYD_STEINBERG, DITHER_ORDERED, QUANTIZE_K_MEANS};
+
+const K_MEANS_ITERATIONS: u32 = 16;
+
+pub type Rgb = (u8, u8, u8);
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum Dither {
+    None,
+    Ordered,
+    FloydSteinberg,
+}
+
+impl Dither {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            DITHER_ORDERED => Self::Ordered,
+            DITHER_FLOYD_STEINBERG => Self::FloydSteinberg,
+            _ => Self::None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum Quantize {
+    MedianCut,
+    KMeans,
+}
+
+impl Quantize {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            QUANTIZE_K_MEANS => Self::KMeans,
+            _ => Self::MedianCut,
+        }
+    }
+}
+
+pub fn rgb_to_rgb24(rgb: Rgb) -> Rgb24 {
+    ((rgb.0 as Rgb24) << 16) | ((rgb.1 as Rgb24) << 8) | rgb.2 as Rgb24
+}
+
+pub fn rgb24_to_rgb(rgb: Rgb24) -> Rgb {
+    ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+pub fn color_dist(rgb1: Rgb, rgb2: Rgb) -> f64 {
+    let (r1, g1, b1) = rgb1;
+    let (r2, g2, b2) = rgb2;
+    let dx = (r1 as f64 - r2 as f64) * 0.30;
+    let dy = (g1 as f64 - g2 as f64) * 0.59;
+    let dz = (b1 as f64 - b2 as f64) * 0.11;
+
+    dx * dx + dy * dy + dz * dz
+}
+
+pub fn generate_palette(pixels: &[Option<Rgb>], num_colors: usize, quantize: Quantize) -> Vec<Rgb> {
+    let mut histogram: Vec<(Rgb, u32)> = Vec::new();
+    let mut histogram_index = HashMap::new();
+    for rgb in pixels.iter().flatten() {
+        let index = *histogram_index.entry(*rgb).or_insert_with(|| {
+            histogram.push((*rgb, 0));
+            histogram.len() - 1
+        });
+        histogram[index].1 += 1;
+    }
+
+    // Images that already fit keep their exact colors in order of appearance
+    if histogram.len() <= num_colors {
+        return histogram.into_iter().map(|(rgb, _)| rgb).collect();
+    }
+
+    let palette = median_cut(&histogram, num_colors);
+    match quantize {
+        Quantize::MedianCut => palette,
+        Quantize::KMeans => k_means(&histogram, palette),
+    }
+}
+
+pub fn remap(
+    pixels: &[Option<Rgb>],
+    width: u32,
+    palette: &[Rgb],
+    transparent: Option<Color>,
+    dither: Dither,
+) -> Vec<Color> {
+    let transparent_color = transparent.unwrap_or(0);
+    let nearest_color = |rgb: Rgb| -> Color {
+        let mut closest_color = 0;
+        let mut closest_dist = f64::MAX;
+        for (i, pal_rgb) in palette.iter().enumerate() {
+            if transparent == Some(i as Color) {
+                continue;
+            }
+            let dist = color_dist(rgb, *pal_rgb);
+            if dist < closest_dist {
+                closest_color = i as Color;
+                closest_dist = dist;
+            }
+        }
+        closest_color
+    };
+
+    match dither {
+        Dither::None => {
+            let mut color_table = HashMap::new();
+            pixels
+                .iter()
+                .map(|rgb| {
+                    rgb.map_or(transparent_color, |rgb| {
+                        *color_table.entry(rgb).or_insert_with(|| nearest_color(rgb))
+                    })
+                })
+                .collect()
+        }
+
+        Dither::Ordered => {
+            let spread = 128.0 / (palette.len().max(1) as f32).cbrt();
+            pixels
+                .iter()
+                .enumerate()
+                .map(|(i, rgb)| {
+                    rgb.map_or(transparent_color, |rgb| {
+                        let x = i % width as usize;
+                        let y = i / width as usize;
+                        let offset = (DITHERING_MATRIX[y % 4][x % 4] - 0.5) * spread;
+                        let shift = |value: u8| (value as f32 + offset).clamp(0.0, 255.0) as u8;
+                        nearest_color((shift(rgb.0), shift(rgb.1), shift(rgb.2)))
+                    })
+                })
+                .collect()
+        }
+
+        Dither::FloydSteinberg => {
+            let width = width as usize;
+            let height = pixels.len() / width.max(1);
+            let mut errors = vec![[0.0_f32; 3]; pixels.len()];
+            let mut data = vec![transparent_color; pixels.len()];
+
+            for y in 0..height {
+                for x in 0..width {
+                    let i = y * width + x;
+                    let Some(rgb) = pixels[i] else {
+                        continue;
+                    };
+
+                    let value = [
+                        (rgb.0 as f32 + errors[i][0]).clamp(0.0, 255.0),
+                        (rgb.1 as f32 + errors[i][1]).clamp(0.0, 255.0),
+                        (rgb.2 as f32 + errors[i][2]).clamp(0.0, 255.0),
+                    ];
+                    let color = nearest_color((value[0] as u8, value[1] as u8, value[2] as u8));
+                    data[i] = color;
+
+                    let pal_rgb = palette[color as usize];
+                    let error = [
+                        value[0] - pal_rgb.0 as f32,
+                        value[1] - pal_rgb.1 as f32,
+                        value[2] - pal_rgb.2 as f32,
+                    ];
+                    for (dx, dy, weight) in [
+                        (1, 0, 7.0 / 16.0),
+                        (-1, 1, 3.0 / 16.0),
+                        (0, 1, 5.0 / 16.0),
+                        (1, 1, 1.0 / 16.0),
+                    ] {
+                        let nx = x as i32 + dx;
+                        let ny = y + dy;
+                        if nx < 0 || nx >= width as i32 || ny >= height {
+                            continue;
+                        }
+                        let ni = ny * width + nx as usize;
+                        for c in 0..3 {
+                            errors[ni][c] += error[c] * weight;
+                        }
+                    }
+                }
+            }
+
+            data
+        }
+    }
+}
+
+fn median_cut(histogram: &[(Rgb, u32)], num_colors: usize) -> Vec<Rgb> {
+    fn channel(rgb: Rgb, index: usize) -> u8 {
+        match index {
+            0 => rgb.0,
+            1 => rgb.1,
+            _ => rgb.2,
+        }
+    }
+
+    fn widest_channel(colors: &[(Rgb, u32)]) -> (usize, u8) {
+        (0..3)
+            .map(|index| {
+                let values = colors.iter().map(|(rgb, _)| channel(*rgb, index));
+                let min = values.clone().min().unwrap_or(0);
+                let max = values.max().unwrap_or(0);
+                (index, max - min)
+            })
+            .max_by_key(|(_, range)| *range)
+            .unwrap()
+    }
+
+    let mut boxes = vec![histogram.to_vec()];
+    while boxes.len() < num_colors {
+        let Some((box_index, (channel_index, _))) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| (i, widest_channel(colors)))
+            .max_by_key(|(_, (_, range))| *range)
+        else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(box_index);
+        colors.sort_by_key(|(rgb, _)| channel(*rgb, channel_index));
+
+        // Split at the weighted median so that dense regions get more colors
+        let total: u64 = colors.iter().map(|(_, count)| *count as u64).sum();
+        let mut sum = 0;
+        let mut split = 1;
+        for (i, (_, count)) in colors.iter().enumerate() {
+            sum += *count as u64;
+            if sum * 2 >= total {
+                split = (i + 1).clamp(1, colors.len() - 1);
+                break;
+            }
+        }
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|colors| average_color(colors)).collect()
+}
+
+fn k_means(histogram: &[(Rgb, u32)], mut centroids: Vec<Rgb>) -> Vec<Rgb> {
+    for _ in 0..K_MEANS_ITERATIONS {
+        let mut clusters = vec![Vec::new(); centroids.len()];
+        for &(rgb, count) in histogram {
+            let nearest = centroids
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| color_dist(rgb, **a).total_cmp(&color_dist(rgb, **b)))
+                .map_or(0, |(i, _)| i);
+            clusters[nearest].push((rgb, count));
+        }
+
+        let new_centroids: Vec<Rgb> = clusters
+            .iter()
+            .zip(&centroids)
+            .map(|(colors, centroid)| {
+                if colors.is_empty() {
+                    *centroid
+                } else {
+                    average_color(colors)
+                }
+            })
+            .collect();
+        if new_centroids == centroids {
+            break;
+        }
+        centroids = new_centroids;
+    }
+
+    centroids
+}
+
+fn average_color(colors: &[(Rgb, u32)]) -> Rgb {
+    let mut sum = [0_u64; 3];
+    let mut total = 0_u64;
+    for &(rgb, count) in colors {
+        let count = count as u64;
+        sum[0] += rgb.0 as u64 * count;
+        sum[1] += rgb.1 as u64 * count;
+        sum[2] += rgb.2 as u64 * count;
+        total += count;
+    }
+
+    let total = total.max(1);
+    (
+        (sum[0] / total) as u8,
+        (sum[1] / total) as u8,
+        (sum[2] / total) as u8,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_palette() {
+        let pixels: Vec<Option<Rgb>> = (0..=255)
+            .map(|value| Some((value, value, value)))
+            .chain([None])
+            .collect();
+        let palette = generate_palette(&pixels, 4, Quantize::MedianCut);
+        assert_eq!(palette.len(), 4);
+        assert_eq!(generate_palette(&pixels, 4, Quantize::KMeans).len(), 4);
+
+        let palette = generate_palette(&[Some((9, 9, 9)), Some((1, 2, 3))], 4, Quantize::KMeans);
+        assert_eq!(palette, vec![(9, 9, 9), (1, 2, 3)]);
+    }
+
+    #[test]
+    fn test_remap() {
+        let palette = [(0, 0, 0), (255, 255, 255), (255, 0, 0)];
+        let pixels = [Some((250, 10, 10)), None, Some((128, 128, 128))];
+        assert_eq!(
+            remap(&pixels, 3, &palette, Some(0), Dither::None),
+            vec![2, 0, 1]
+        );
+
+        let pixels = vec![Some((128, 128, 128)); 16];
+        let data = remap(&pixels, 4, &palette[..2], None, Dither::FloydSteinberg);
+        let num_white = data.iter().filter(|color| **color == 1).count();
+        assert!((6..=10).contains(&num_white));
+    }
+}
@@ -10,6 +10,7 @@ use zip::write::SimpleFileOptions;
 use zip::{ZipArchive, ZipWriter};
 
 use crate::image::{Color, Image, Rgb24};
+use crate::image_transform::Upscale;
 use crate::pyxel::Pyxel;
 use crate::resource_data::{ResourceData1, ResourceData2};
 use crate::screencast::Screencast;
@@ -141,10 +142,12 @@ impl Pyxel {
         pyxel_platform::emscripten::save_file(filename);
     }
 
-    pub fn screenshot(&mut self, scale: Option<u32>) {
+    pub fn screenshot(&mut self, scale: Option<u32>, upscale: Option<Upscale>) {
         let filename = Self::prepend_desktop_path(&format!("pyxel-{}", Self::datetime_string()));
         let scale = max(scale.unwrap_or(self.resource.capture_scale), 1);
-        self.screen.lock().save(&filename, scale);
+        self.screen
+            .lock()
+            .save(&filename, scale, None, None, upscale);
 
         #[cfg(target_os = "emscripten")]
         pyxel_platform::emscripten::save_file(&(filename + ".png"));
@@ -198,7 +201,7 @@ impl Pyxel {
         let filename = Self::prepend_desktop_path(&format!("pyxel-image{image_index}"));
 
         if let Some(image) = self.images.lock().get(image_index as usize) {
-            image.lock().save(&filename, 1);
+            image.lock().save(&filename, 1, None, None, None);
 
             #[cfg(target_os = "emscripten")]
             pyxel_platform::emscripten::save_file(&(filename + ".png"));
@@ -216,7 +219,7 @@ impl Pyxel {
                 image.pset(i as f64, 0.0, i as Color);
             }
 
-            image.save(&filename, 16);
+            image.save(&filename, 16, None, None, None);
 
             #[cfg(target_os = "emscripten")]
             pyxel_platform::emscripten::save_file(&(filename + ".png"));
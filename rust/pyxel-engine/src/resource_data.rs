@@ -59,6 +59,8 @@ struct TilemapData {
     height: u32,
     imgsrc: u32,
     data: Vec<Vec<ImageTileCoord>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attrs: Vec<[u32; 3]>,
 }
 
 impl TilemapData {
@@ -83,11 +85,19 @@ impl TilemapData {
             .collect();
         let data = compress_vec2(&data);
 
+        let mut attrs: Vec<_> = tilemap
+            .attributes
+            .iter()
+            .map(|((u, v), flags)| [*u as u32, *v as u32, *flags])
+            .collect();
+        attrs.sort_unstable();
+
         Self {
             width,
             height,
             imgsrc,
             data,
+            attrs,
         }
     }
 
@@ -99,6 +109,11 @@ impl TilemapData {
             let mut tilemap = tilemap.lock();
             let data: Vec<_> = data.clone().into_iter().flatten().collect();
             tilemap.canvas.data = data.chunks(2).map(|chunk| (chunk[0], chunk[1])).collect();
+            tilemap.attributes = self
+                .attrs
+                .iter()
+                .map(|[u, v, flags]| ((*u as ImageTileCoord, *v as ImageTileCoord), *flags))
+                .collect();
         }
 
         tilemap
@@ -0,0 +1,159 @@
+use std::f64::consts::TAU;
+
+use crate::image::Color;
+use crate::settings::{IMAGE_SIZE, MAX_COLORS, TILE_SIZE};
+
+const WAVE_PERIOD: f64 = 16.0;
+const WAVE_PHASE_STEP: f64 = 2.0;
+const WAVE_AMPLITUDE: f64 = 1.5;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum TextEffect {
+    None,
+    Wave,
+    Shake,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum RichToken {
+    Char(char),
+    Color(Color),
+    ColorEnd,
+    Icon(u32, u32),
+    Effect(TextEffect),
+    EffectEnd,
+}
+
+pub fn parse_rich_text(string: &str) -> Vec<RichToken> {
+    let mut tokens = Vec::new();
+    let mut chars = string.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            tokens.push(RichToken::Char(c));
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            tokens.push(RichToken::Char('{'));
+            continue;
+        }
+
+        let mut tag = String::new();
+        let mut is_closed = false;
+        for c in chars.by_ref() {
+            if c == '}' {
+                is_closed = true;
+                break;
+            }
+            tag.push(c);
+        }
+
+        if let Some(token) = is_closed.then(|| parse_tag(&tag)).flatten() {
+            tokens.push(token);
+        } else {
+            tokens.push(RichToken::Char('{'));
+            tokens.extend(tag.chars().map(RichToken::Char));
+            if is_closed {
+                tokens.push(RichToken::Char('}'));
+            }
+        }
+    }
+
+    tokens
+}
+
+fn parse_tag(tag: &str) -> Option<RichToken> {
+    match tag {
+        "/c" => return Some(RichToken::ColorEnd),
+        "wave" => return Some(RichToken::Effect(TextEffect::Wave)),
+        "shake" => return Some(RichToken::Effect(TextEffect::Shake)),
+        "/wave" | "/shake" => return Some(RichToken::EffectEnd),
+        _ => {}
+    }
+
+    if let Some(color) = tag.strip_prefix('c') {
+        return color
+            .parse()
+            .ok()
+            .filter(|&color: &Color| (color as u32) < MAX_COLORS)
+            .map(RichToken::Color);
+    }
+
+    if let Some(icon) = tag.strip_prefix("icon:") {
+        let values: Vec<u32> = icon.split(':').map_while(|v| v.parse().ok()).collect();
+        return match values[..] {
+            [index] => Some(RichToken::Icon(0, index)),
+            [image_index, index] => Some(RichToken::Icon(image_index, index)),
+            _ => None,
+        };
+    }
+
+    None
+}
+
+pub fn icon_coord(index: u32) -> (u32, u32) {
+    let num_cols = IMAGE_SIZE / TILE_SIZE;
+    (
+        (index % num_cols) * TILE_SIZE,
+        (index / num_cols) * TILE_SIZE,
+    )
+}
+
+pub fn effect_offset(effect: TextEffect, char_index: u32, frame_count: u32) -> (i32, i32) {
+    match effect {
+        TextEffect::None => (0, 0),
+        TextEffect::Wave => {
+            let phase = (frame_count as f64 + char_index as f64 * WAVE_PHASE_STEP) / WAVE_PERIOD;
+            (0, (f64::sin(phase * TAU) * WAVE_AMPLITUDE).round() as i32)
+        }
+        TextEffect::Shake => {
+            let hash = (frame_count.wrapping_mul(0x9E37_79B9)
+                ^ char_index.wrapping_mul(0x85EB_CA6B))
+            .wrapping_mul(0xC2B2_AE35);
+            ((hash >> 8) as i32 % 3 - 1, (hash >> 16) as i32 % 3 - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rich_text() {
+        assert_eq!(
+            parse_rich_text("a{c8}b{/c}{icon:3}{icon:1:2}"),
+            vec![
+                RichToken::Char('a'),
+                RichToken::Color(8),
+                RichToken::Char('b'),
+                RichToken::ColorEnd,
+                RichToken::Icon(0, 3),
+                RichToken::Icon(1, 2),
+            ]
+        );
+        assert_eq!(
+            parse_rich_text("{{x}{wave}"),
+            vec![
+                RichToken::Char('{'),
+                RichToken::Char('x'),
+                RichToken::Char('}'),
+                RichToken::Effect(TextEffect::Wave),
+            ]
+        );
+        assert_eq!(parse_rich_text("{zz}").len(), 4);
+        assert_eq!(parse_rich_text("{c8").len(), 3);
+        assert_eq!(parse_rich_text("{c254}"), vec![RichToken::Color(254)]);
+        assert_eq!(parse_rich_text("{c255}").len(), 6);
+    }
+
+    #[test]
+    fn test_effect_offset() {
+        assert_eq!(effect_offset(TextEffect::None, 3, 10), (0, 0));
+        for frame_count in 0..32 {
+            let (dx, dy) = effect_offset(TextEffect::Shake, 5, frame_count);
+            assert!((-1..=1).contains(&dx) && (-1..=1).contains(&dy));
+        }
+    }
+}
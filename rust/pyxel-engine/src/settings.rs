@@ -55,6 +55,7 @@ pub const IMAGE_SIZE: u32 = 256;
 pub const NUM_TILEMAPS: u32 = 8;
 pub const TILEMAP_SIZE: u32 = 256;
 pub const TILE_SIZE: u32 = 8;
+pub const TILEMAP_CHUNK_SIZE: u32 = 64;
 pub const DEFAULT_COLORS: [Rgb24; NUM_COLORS as usize] = [
     0x000000, 0x2b335f, 0x7e2072, 0x19959c, 0x8b4852, 0x395c98, 0xa9c1ff, 0xeeeeee, //
     0xd4186c, 0xd38441, 0xe9c35b, 0x70c6a9, 0x7696de, 0xa3a3a3, 0xFF9798, 0xedc7b0,
@@ -83,6 +84,9 @@ pub const CURSOR_DATA: [&str; CURSOR_HEIGHT as usize] = [
 pub const MIN_FONT_CODE: char = 32 as char;
 pub const MAX_FONT_CODE: char = 127 as char;
 pub const NUM_FONT_ROWS: u32 = 16;
+pub const MAX_FONT_GLYPHS: u32 = 512;
+pub const DEFAULT_FONT_SIZE: f64 = 8.0;
+pub const MAX_GLYPH_WIDTH: u32 = 32;
 pub const FONT_WIDTH: u32 = 4;
 pub const FONT_HEIGHT: u32 = 6;
 pub const FONT_DATA: [u32; MAX_FONT_CODE as usize - MIN_FONT_CODE as usize + 1] = [
@@ -98,8 +102,156 @@ pub const FONT_DATA: [u32; MAX_FONT_CODE as usize - MIN_FONT_CODE as usize + 1]
     0x06aa62, 0x068880, 0x06c6c0, 0x4e4460, 0x0aaa60, 0x0aaa40, 0x0aaee0, 0x0a44a0, 0x0aa624,
     0x0e24e0, 0x64c460, 0x444440, 0xc464c0, 0x6c0000, 0xeeeee0,
 ];
+pub const FONT_EXT_DATA: [(char, u32); 125] = [
+    ('\u{00a0}', 0x000000),
+    ('\u{00a1}', 0x404440),
+    ('\u{00a2}', 0x04e8e4),
+    ('\u{00a3}', 0x64e4e0),
+    ('\u{00a4}', 0x0a4a00),
+    ('\u{00a5}', 0xaa4e40),
+    ('\u{00a6}', 0x440440),
+    ('\u{00a7}', 0x6ca6c0),
+    ('\u{00a8}', 0xa00000),
+    ('\u{00a9}', 0xe8e000),
+    ('\u{00aa}', 0x6a60e0),
+    ('\u{00ab}', 0x005a50),
+    ('\u{00ac}', 0x00e200),
+    ('\u{00ad}', 0x00c000),
+    ('\u{00ae}', 0xeca000),
+    ('\u{00af}', 0xe00000),
+    ('\u{00b0}', 0x4a4000),
+    ('\u{00b1}', 0x4e40e0),
+    ('\u{00b2}', 0xc4c000),
+    ('\u{00b3}', 0xccc000),
+    ('\u{00b4}', 0x240000),
+    ('\u{00b5}', 0x0aaae8),
+    ('\u{00b6}', 0xee6660),
+    ('\u{00b7}', 0x004000),
+    ('\u{00b8}', 0x00004c),
+    ('\u{00b9}', 0xc4e000),
+    ('\u{00ba}', 0x4a40e0),
+    ('\u{00bb}', 0x00a5a0),
+    ('\u{00bc}', 0x8a2a20),
+    ('\u{00bd}', 0x8a0c60),
+    ('\u{00be}', 0xcac620),
+    ('\u{00bf}', 0x404860),
+    ('\u{00c0}', 0x84aea0),
+    ('\u{00c1}', 0x24aea0),
+    ('\u{00c2}', 0xe4aea0),
+    ('\u{00c3}', 0x64aea0),
+    ('\u{00c4}', 0xa4aea0),
+    ('\u{00c5}', 0x44aea0),
+    ('\u{00c6}', 0x7afab0),
+    ('\u{00c7}', 0x688864),
+    ('\u{00c8}', 0x8ec8e0),
+    ('\u{00c9}', 0x2ec8e0),
+    ('\u{00ca}', 0xeec8e0),
+    ('\u{00cb}', 0xaec8e0),
+    ('\u{00cc}', 0x8e44e0),
+    ('\u{00cd}', 0x2e44e0),
+    ('\u{00ce}', 0xee44e0),
+    ('\u{00cf}', 0xae44e0),
+    ('\u{00d0}', 0xcaeac0),
+    ('\u{00d1}', 0x6caaa0),
+    ('\u{00d2}', 0x8eaae0),
+    ('\u{00d3}', 0x2eaae0),
+    ('\u{00d4}', 0xeeaae0),
+    ('\u{00d5}', 0x6eaae0),
+    ('\u{00d6}', 0xaeaae0),
+    ('\u{00d7}', 0x0a4a00),
+    ('\u{00d8}', 0x6aeac0),
+    ('\u{00d9}', 0x8aaae0),
+    ('\u{00da}', 0x2aaae0),
+    ('\u{00db}', 0xeaaae0),
+    ('\u{00dc}', 0xaaaae0),
+    ('\u{00dd}', 0x2aa440),
+    ('\u{00de}', 0x8cac80),
+    ('\u{00df}', 0x4acac8),
+    ('\u{00e0}', 0x86aa60),
+    ('\u{00e1}', 0x26aa60),
+    ('\u{00e2}', 0xe6aa60),
+    ('\u{00e3}', 0x66aa60),
+    ('\u{00e4}', 0xa6aa60),
+    ('\u{00e5}', 0x46aa60),
+    ('\u{00e6}', 0x07bc70),
+    ('\u{00e7}', 0x068864),
+    ('\u{00e8}', 0x86ac60),
+    ('\u{00e9}', 0x26ac60),
+    ('\u{00ea}', 0xe6ac60),
+    ('\u{00eb}', 0xa6ac60),
+    ('\u{00ec}', 0x804440),
+    ('\u{00ed}', 0x204440),
+    ('\u{00ee}', 0xe04440),
+    ('\u{00ef}', 0xa04440),
+    ('\u{00f0}', 0x626a40),
+    ('\u{00f1}', 0x6caaa0),
+    ('\u{00f2}', 0x84aa40),
+    ('\u{00f3}', 0x24aa40),
+    ('\u{00f4}', 0xe4aa40),
+    ('\u{00f5}', 0x64aa40),
+    ('\u{00f6}', 0xa4aa40),
+    ('\u{00f7}', 0x40e040),
+    ('\u{00f8}', 0x06aac0),
+    ('\u{00f9}', 0x8aaa60),
+    ('\u{00fa}', 0x2aaa60),
+    ('\u{00fb}', 0xeaaa60),
+    ('\u{00fc}', 0xaaaa60),
+    ('\u{00fd}', 0x2aa624),
+    ('\u{00fe}', 0x8caac8),
+    ('\u{00ff}', 0xa0a624),
+    ('\u{2190}', 0x26e620),
+    ('\u{2191}', 0x4e4440),
+    ('\u{2192}', 0x8cec80),
+    ('\u{2193}', 0x444e40),
+    ('\u{2500}', 0x00f000),
+    ('\u{2502}', 0x444444),
+    ('\u{250c}', 0x007444),
+    ('\u{2510}', 0x00c444),
+    ('\u{2514}', 0x447000),
+    ('\u{2518}', 0x44c000),
+    ('\u{251c}', 0x447444),
+    ('\u{2524}', 0x44c444),
+    ('\u{252c}', 0x00f444),
+    ('\u{2534}', 0x44f000),
+    ('\u{253c}', 0x44f444),
+    ('\u{2550}', 0x0f0f00),
+    ('\u{2551}', 0xaaaaaa),
+    ('\u{2554}', 0x078baa),
+    ('\u{2557}', 0x0e2aaa),
+    ('\u{255a}', 0xaab870),
+    ('\u{255d}', 0xaaa2e0),
+    ('\u{2580}', 0xfff000),
+    ('\u{2584}', 0x000fff),
+    ('\u{2588}', 0xffffff),
+    ('\u{258c}', 0xcccccc),
+    ('\u{2590}', 0x333333),
+    ('\u{2591}', 0x828282),
+    ('\u{2592}', 0xa5a5a5),
+    ('\u{2593}', 0x7d7d7d),
+];
 pub const NUM_SCREEN_TYPES: u32 = 3;
 
+pub const ALIGN_LEFT: u32 = 0;
+pub const ALIGN_CENTER: u32 = 1;
+pub const ALIGN_RIGHT: u32 = 2;
+pub const STENCIL_OFF: u32 = 0;
+pub const STENCIL_TEST: u32 = 1;
+pub const STENCIL_WRITE: u32 = 2;
+pub const DITHER_NONE: u32 = 0;
+pub const DITHER_ORDERED: u32 = 1;
+pub const DITHER_FLOYD_STEINBERG: u32 = 2;
+pub const QUANTIZE_MEDIAN_CUT: u32 = 0;
+pub const QUANTIZE_K_MEANS: u32 = 1;
+pub const UPSCALE_NONE: u32 = 0;
+pub const UPSCALE_SCALE2X: u32 = 1;
+pub const UPSCALE_SCALE3X: u32 = 2;
+pub const UPSCALE_EPX: u32 = 3;
+pub const AUTOTILE_BLOB16: u32 = 0;
+pub const AUTOTILE_BLOB47: u32 = 1;
+pub const LOOP_ONCE: u32 = 0;
+pub const LOOP_REPEAT: u32 = 1;
+pub const LOOP_PING_PONG: u32 = 2;
+
 // Audio
 pub const CLOCK_RATE: u32 = 2_048_000; // 2.048MHz
 pub const SAMPLE_RATE: u32 = 22050; // 22.05kHz
@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+
+use crate::aseprite_parser::{AsepriteData, AsepriteDirection};
+use crate::image::Color;
+use crate::pyxel::{Pyxel, FPS};
+use crate::settings::{LOOP_ONCE, LOOP_PING_PONG};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AnimationFrame {
+    pub img: u32,
+    pub u: f64,
+    pub v: f64,
+    pub w: f64,
+    pub h: f64,
+    pub duration: u32,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LoopMode {
+    Once,
+    Loop,
+    PingPong,
+}
+
+impl LoopMode {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            LOOP_ONCE => Self::Once,
+            LOOP_PING_PONG => Self::PingPong,
+            _ => Self::Loop,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    pub loop_mode: LoopMode,
+}
+
+pub struct Sprite {
+    pub animations: BTreeMap<String, Animation>,
+    current: Option<String>,
+    start_frame: u32,
+    paused_frame: Option<u32>,
+}
+
+impl Animation {
+    pub fn new(frames: &[AnimationFrame], loop_mode: LoopMode) -> Self {
+        Self {
+            frames: frames.to_vec(),
+            loop_mode,
+        }
+    }
+
+    pub fn from_aseprite(data: &AsepriteData, img: u32, tag: Option<&str>) -> Option<Self> {
+        // Aseprite durations are in milliseconds and converted to frames
+        let fps = FPS.load(Ordering::Relaxed);
+        let to_frame = |index: usize| {
+            let frame = &data.frames[index];
+            AnimationFrame {
+                img,
+                u: frame.u as f64,
+                v: frame.v as f64,
+                w: data.width as f64,
+                h: data.height as f64,
+                duration: (frame.duration * fps).div_ceil(1000).max(1),
+            }
+        };
+
+        let Some(tag) = tag else {
+            let frames: Vec<_> = (0..data.frames.len()).map(to_frame).collect();
+            return Some(Self::new(&frames, LoopMode::Loop));
+        };
+        let tag = data.tags.iter().find(|data_tag| data_tag.name == tag)?;
+        let mut frames: Vec<_> = (tag.from as usize..=tag.to as usize)
+            .filter(|index| *index < data.frames.len())
+            .map(to_frame)
+            .collect();
+        if matches!(
+            tag.direction,
+            AsepriteDirection::Reverse | AsepriteDirection::PingPongReverse
+        ) {
+            frames.reverse();
+        }
+        let loop_mode = match tag.direction {
+            AsepriteDirection::PingPong | AsepriteDirection::PingPongReverse => LoopMode::PingPong,
+            _ if tag.repeat == 1 => LoopMode::Once,
+            _ => LoopMode::Loop,
+        };
+        Some(Self::new(&frames, loop_mode))
+    }
+
+    pub fn frame_index(&self, elapsed: u32) -> Option<usize> {
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        // Ping-pong plays the inner frames again in reverse without repeating the ends
+        let num_frames = self.frames.len();
+        let num_steps = match self.loop_mode {
+            LoopMode::PingPong if num_frames > 2 => num_frames * 2 - 2,
+            _ => num_frames,
+        };
+        let step_index = |step: usize| {
+            if step < num_frames {
+                step
+            } else {
+                num_frames * 2 - 2 - step
+            }
+        };
+        let total: u32 = (0..num_steps)
+            .map(|step| self.frames[step_index(step)].duration.max(1))
+            .sum();
+
+        let mut time = match self.loop_mode {
+            LoopMode::Once if elapsed >= total => return Some(num_frames - 1),
+            LoopMode::Once => elapsed,
+            LoopMode::Loop | LoopMode::PingPong => elapsed % total,
+        };
+        for step in 0..num_steps {
+            let index = step_index(step);
+            let duration = self.frames[index].duration.max(1);
+            if time < duration {
+                return Some(index);
+            }
+            time -= duration;
+        }
+        None
+    }
+
+    pub fn is_finished(&self, elapsed: u32) -> bool {
+        self.loop_mode == LoopMode::Once
+            && elapsed
+                >= self
+                    .frames
+                    .iter()
+                    .map(|frame| frame.duration.max(1))
+                    .sum::<u32>()
+    }
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sprite {
+    pub fn new() -> Self {
+        Self {
+            animations: BTreeMap::new(),
+            current: None,
+            start_frame: 0,
+            paused_frame: None,
+        }
+    }
+
+    pub fn add_animation(&mut self, name: &str, animation: Animation) {
+        self.animations.insert(name.to_string(), animation);
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    pub fn play(
+        &mut self,
+        name: &str,
+        frame_count: u32,
+        restart: Option<bool>,
+    ) -> Result<(), String> {
+        if !self.animations.contains_key(name) {
+            return Err(format!("Animation '{name}' not found in sprite"));
+        }
+        if self.current.as_deref() == Some(name) && !restart.unwrap_or(false) {
+            self.resume(frame_count);
+            return Ok(());
+        }
+        self.current = Some(name.to_string());
+        self.start_frame = frame_count;
+        self.paused_frame = None;
+        Ok(())
+    }
+
+    pub fn pause(&mut self, frame_count: u32) {
+        if self.paused_frame.is_none() {
+            self.paused_frame = Some(frame_count);
+        }
+    }
+
+    pub fn resume(&mut self, frame_count: u32) {
+        if let Some(paused_frame) = self.paused_frame.take() {
+            self.start_frame += frame_count.saturating_sub(paused_frame);
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some() && self.paused_frame.is_none()
+    }
+
+    pub fn is_finished(&self, frame_count: u32) -> bool {
+        self.animation()
+            .is_some_and(|animation| animation.is_finished(self.elapsed(frame_count)))
+    }
+
+    pub fn frame(&self, frame_count: u32) -> Option<AnimationFrame> {
+        let animation = self.animation()?;
+        let index = animation.frame_index(self.elapsed(frame_count))?;
+        Some(animation.frames[index])
+    }
+
+    fn animation(&self) -> Option<&Animation> {
+        self.current
+            .as_ref()
+            .and_then(|name| self.animations.get(name))
+    }
+
+    fn elapsed(&self, frame_count: u32) -> u32 {
+        self.paused_frame
+            .unwrap_or(frame_count)
+            .saturating_sub(self.start_frame)
+    }
+}
+
+impl Pyxel {
+    pub fn draw_sprite(
+        &self,
+        x: f64,
+        y: f64,
+        sprite: &Sprite,
+        flip_x: Option<bool>,
+        flip_y: Option<bool>,
+        color_key: Option<Color>,
+        rotate: Option<f64>,
+        scale: Option<f64>,
+    ) {
+        let Some(frame) = sprite.frame(self.frame_count) else {
+            return;
+        };
+
+        // Negative sizes make blt mirror the frame
+        let w = if flip_x.unwrap_or(false) {
+            -frame.w
+        } else {
+            frame.w
+        };
+        let h = if flip_y.unwrap_or(false) {
+            -frame.h
+        } else {
+            frame.h
+        };
+        self.blt(
+            x, y, frame.img, frame.u, frame.v, w, h, color_key, rotate, scale,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(durations: &[u32]) -> Vec<AnimationFrame> {
+        durations
+            .iter()
+            .enumerate()
+            .map(|(i, &duration)| AnimationFrame {
+                img: 0,
+                u: i as f64 * 16.0,
+                v: 0.0,
+                w: 16.0,
+                h: 16.0,
+                duration,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_loop_modes() {
+        let once = Animation::new(&frames(&[2, 3]), LoopMode::Once);
+        assert_eq!(once.frame_index(1), Some(0));
+        assert_eq!(once.frame_index(4), Some(1));
+        assert_eq!(once.frame_index(100), Some(1));
+        assert!(once.is_finished(5));
+
+        let looped = Animation::new(&frames(&[2, 3]), LoopMode::Loop);
+        assert_eq!(looped.frame_index(5), Some(0));
+        assert!(!looped.is_finished(100));
+
+        let ping_pong = Animation::new(&frames(&[1, 1, 1]), LoopMode::PingPong);
+        let indices: Vec<_> = (0..6).filter_map(|t| ping_pong.frame_index(t)).collect();
+        assert_eq!(indices, vec![0, 1, 2, 1, 0, 1]);
+
+        let ping_pong = Animation::new(&frames(&[1, 2, 1, 3]), LoopMode::PingPong);
+        let indices: Vec<_> = (0..12).filter_map(|t| ping_pong.frame_index(t)).collect();
+        assert_eq!(indices, vec![0, 1, 1, 2, 3, 3, 3, 2, 1, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_play_pause() {
+        let mut sprite = Sprite::new();
+        sprite.add_animation("walk", Animation::new(&frames(&[4, 4]), LoopMode::Loop));
+
+        assert!(sprite.play("run", 0, None).is_err());
+        sprite.play("walk", 10, None).unwrap();
+        assert_eq!(sprite.frame(15).unwrap().u, 16.0);
+
+        sprite.pause(15);
+        assert!(!sprite.is_playing());
+        assert_eq!(sprite.frame(100).unwrap().u, 16.0);
+
+        sprite.play("walk", 20, None).unwrap();
+        assert_eq!(sprite.frame(22).unwrap().u, 16.0);
+        assert_eq!(sprite.frame(23).unwrap().u, 0.0);
+
+        sprite.play("walk", 30, Some(true)).unwrap();
+        assert_eq!(sprite.frame(30).unwrap().u, 0.0);
+    }
+}
@@ -232,7 +232,7 @@ impl Pyxel {
                 }
             } else if self.btnp(KEY_1, None, None) {
                 self.reset_key(KEY_1);
-                self.screenshot(None);
+                self.screenshot(None, None);
             } else if self.btnp(KEY_2, None, None) {
                 self.reset_key(KEY_2);
                 self.reset_screencast();
@@ -312,6 +312,7 @@ impl Pyxel {
         }
 
         self.check_special_input();
+        self.update_palette_cycles();
 
         if let Some(callback) = callback {
             callback.update(self);
@@ -319,6 +320,9 @@ impl Pyxel {
                 .update_profiler
                 .end(pyxel_platform::elapsed_time());
         }
+
+        self.update_emitters();
+        self.update_view_camera();
     }
 
     fn draw_perf_monitor(&self) {
@@ -420,6 +424,9 @@ impl Pyxel {
         }
 
         self.system.watch_info.update();
+        self.composite_layers();
+        self.zoom_view_camera();
+        self.view_camera.zoomed = false;
         self.draw_perf_monitor();
         self.draw_cursor();
         self.render_screen();
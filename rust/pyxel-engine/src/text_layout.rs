@@ -0,0 +1,240 @@
+use crate::font::{builtin_glyph_pos, Font};
+use crate::settings::{ALIGN_CENTER, ALIGN_RIGHT, FONT_HEIGHT, FONT_WIDTH};
+
+const ELLIPSIS: &str = "...";
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            ALIGN_CENTER => Self::Center,
+            ALIGN_RIGHT => Self::Right,
+            _ => Self::Left,
+        }
+    }
+}
+
+pub fn char_width(c: char, font: Option<&Font>) -> i32 {
+    font.map_or_else(
+        || {
+            if builtin_glyph_pos(c).is_some() {
+                FONT_WIDTH as i32
+            } else {
+                0
+            }
+        },
+        |font| font.char_width(c),
+    )
+}
+
+pub fn line_height(font: Option<&Font>) -> i32 {
+    font.map_or(FONT_HEIGHT as i32, Font::line_height)
+}
+
+// Advance of a character after the previous one, matching Font::text_width
+pub fn char_advance(prev: Option<char>, c: char, font: Option<&Font>) -> i32 {
+    let kerning = match (prev, font) {
+        (Some(prev), Some(font)) => font.kerning(prev, c),
+        _ => 0,
+    };
+    kerning + char_width(c, font)
+}
+
+fn str_advance(prev: Option<char>, string: &str, font: Option<&Font>) -> i32 {
+    let mut prev = prev;
+    string
+        .chars()
+        .map(|c| {
+            let advance = char_advance(prev, c, font);
+            prev = Some(c);
+            advance
+        })
+        .sum()
+}
+
+pub fn line_width(line: &str, font: Option<&Font>) -> i32 {
+    str_advance(None, line, font)
+}
+
+pub fn text_width(string: &str, font: Option<&Font>) -> i32 {
+    string
+        .split('\n')
+        .map(|line| line_width(line, font))
+        .max()
+        .unwrap_or(0)
+}
+
+pub fn text_bounds(string: &str, font: Option<&Font>, line_spacing: i32) -> (i32, i32) {
+    let num_lines = string.split('\n').count() as i32;
+    let height = num_lines * line_height(font) + (num_lines - 1) * line_spacing;
+    (text_width(string, font), height)
+}
+
+pub fn wrap_text(string: &str, width: i32, font: Option<&Font>) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in string.split('\n') {
+        let mut line = String::new();
+        let mut cur_width = 0;
+
+        for word in paragraph.split(' ') {
+            if !line.is_empty() {
+                let joined_width =
+                    cur_width + str_advance(line.chars().next_back(), &format!(" {word}"), font);
+                if joined_width <= width {
+                    line.push(' ');
+                    line.push_str(word);
+                    cur_width = joined_width;
+                    continue;
+                }
+                lines.push(line);
+                line = String::new();
+                cur_width = 0;
+            }
+
+            // Words wider than the box are broken between characters
+            for c in word.chars() {
+                let advance = char_advance(line.chars().next_back(), c, font);
+                if !line.is_empty() && cur_width + advance > width {
+                    lines.push(line);
+                    line = String::new();
+                    cur_width = char_advance(None, c, font);
+                } else {
+                    cur_width += advance;
+                }
+                line.push(c);
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+pub fn truncate_text(line: &str, width: i32, font: Option<&Font>, force_ellipsis: bool) -> String {
+    if !force_ellipsis && line_width(line, font) <= width {
+        return line.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut cur_width = 0;
+    let mut prev = None;
+    for c in line.chars() {
+        let advance = char_advance(prev, c, font);
+        if cur_width + advance + str_advance(Some(c), ELLIPSIS, font) > width {
+            break;
+        }
+        truncated.push(c);
+        cur_width += advance;
+        prev = Some(c);
+    }
+    truncated.push_str(ELLIPSIS);
+    truncated
+}
+
+pub fn layout_text(
+    string: &str,
+    width: i32,
+    height: i32,
+    font: Option<&Font>,
+    align: TextAlign,
+    line_spacing: i32,
+    wrap: bool,
+    ellipsis: bool,
+) -> Vec<(i32, i32, String)> {
+    let mut lines = if wrap {
+        wrap_text(string, width, font)
+    } else {
+        string.split('\n').map(ToString::to_string).collect()
+    };
+
+    let line_height = line_height(font);
+    let max_lines = ((height + line_spacing) / (line_height + line_spacing)).max(0) as usize;
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if ellipsis {
+            if let Some(last) = lines.last_mut() {
+                *last = truncate_text(last, width, font, true);
+            }
+        }
+    }
+
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = if ellipsis {
+                truncate_text(&line, width, font, false)
+            } else {
+                line
+            };
+            let offset_x = match align {
+                TextAlign::Left => 0,
+                TextAlign::Center => (width - line_width(&line, font)) / 2,
+                TextAlign::Right => width - line_width(&line, font),
+            };
+            let offset_y = i as i32 * (line_height + line_spacing);
+            (offset_x, offset_y, line)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_bounds() {
+        assert_eq!(text_width("abc", None), 12);
+        assert_eq!(text_bounds("ab\nabcd", None, 2), (16, 14));
+        assert_eq!(text_width("héllo→┼", None), 28);
+        assert_eq!(text_width("\u{3042}", None), 0);
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(wrap_text("aa bb cc", 20, None), vec!["aa bb", "cc"]);
+        assert_eq!(wrap_text("abcdefg", 12, None), vec!["abc", "def", "g"]);
+        assert_eq!(wrap_text("a\n\nb", 40, None), vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn test_kerning_width() {
+        let filename = std::env::temp_dir().join("pyxel_text_layout_test.bdf");
+        std::fs::write(
+            &filename,
+            "FONTBOUNDINGBOX 4 6 0 -1\nSTARTCHAR A\nENCODING 65\nDWIDTH 4 0\nBBX 1 1 0 0\nBITMAP\n80\nENDCHAR\nKPX 65 86 -1\n",
+        )
+        .unwrap();
+        let font = Font::new(filename.to_str().unwrap(), None).unwrap();
+        std::fs::remove_file(filename).unwrap();
+        let font = font.lock();
+
+        assert_eq!(line_width("AVAV", Some(&font)), font.text_width("AVAV"));
+        assert_eq!(wrap_text("AVAV", 7, Some(&font)), vec!["AV", "AV"]);
+        assert_eq!(wrap_text("AV AV", 14, Some(&font)), vec!["AV", "AV"]);
+        for line in wrap_text("AV AV AVA", 18, Some(&font)) {
+            assert!(font.text_width(&line) <= 18);
+        }
+        assert_eq!(truncate_text("AVAVAV", 19, Some(&font), false), "AV...");
+    }
+
+    #[test]
+    fn test_layout_text() {
+        let lines = layout_text("aa bb cc", 20, 6, None, TextAlign::Right, 0, true, true);
+        assert_eq!(lines, vec![(0, 0, "aa...".to_string())]);
+
+        let lines = layout_text("ab\nc", 20, 20, None, TextAlign::Center, 1, true, false);
+        assert_eq!(
+            lines,
+            vec![(6, 0, "ab".to_string()), (8, 7, "c".to_string())]
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::tilemap::{Tile, Tilemap};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TileAnimation {
+    pub frames: Vec<(Tile, u32)>,
+}
+
+impl TileAnimation {
+    pub fn new(frames: &[(Tile, u32)]) -> Self {
+        Self {
+            frames: frames.to_vec(),
+        }
+    }
+
+    pub fn duration(&self) -> u32 {
+        self.frames
+            .iter()
+            .map(|(_, duration)| duration.max(&1))
+            .sum()
+    }
+
+    pub fn frame(&self, time: u32) -> Option<Tile> {
+        let duration = self.duration();
+        if duration == 0 {
+            return None;
+        }
+
+        let mut time = time % duration;
+        for &(tile, frame_duration) in &self.frames {
+            let frame_duration = frame_duration.max(1);
+            if time < frame_duration {
+                return Some(tile);
+            }
+            time -= frame_duration;
+        }
+        None
+    }
+}
+
+impl Tilemap {
+    pub fn set_animation(&mut self, tile: Tile, frames: &[(Tile, u32)]) {
+        if frames.is_empty() {
+            self.animations.remove(&tile);
+        } else {
+            self.animations.insert(tile, TileAnimation::new(frames));
+        }
+    }
+
+    pub fn animated_tile(&self, tile: Tile, time: u32) -> Tile {
+        self.animations
+            .get(&tile)
+            .and_then(|animation| animation.frame(time))
+            .unwrap_or(tile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::ImageSource;
+
+    #[test]
+    fn test_animated_tile() {
+        let tilemap = Tilemap::new(1, 1, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.set_animation((0, 1), &[((0, 1), 10), ((1, 1), 5), ((2, 1), 5)]);
+
+        assert_eq!(tilemap.animated_tile((0, 1), 0), (0, 1));
+        assert_eq!(tilemap.animated_tile((0, 1), 12), (1, 1));
+        assert_eq!(tilemap.animated_tile((0, 1), 19), (2, 1));
+        assert_eq!(tilemap.animated_tile((0, 1), 20), (0, 1));
+        assert_eq!(tilemap.animated_tile((3, 3), 12), (3, 3));
+
+        tilemap.set_animation((0, 1), &[]);
+        assert_eq!(tilemap.animated_tile((0, 1), 12), (0, 1));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::settings::TILE_SIZE;
+use crate::tilemap::Tilemap;
+
+const TILE: f64 = TILE_SIZE as f64;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct SweepResult {
+    pub x: f64,
+    pub y: f64,
+    pub hit_x: bool,
+    pub hit_y: bool,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct RaycastHit {
+    pub x: f64,
+    pub y: f64,
+    pub tile_x: i32,
+    pub tile_y: i32,
+}
+
+impl Tilemap {
+    pub fn rect_tiles(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        mask: u32,
+    ) -> Vec<(i32, i32)> {
+        let mut tiles = Vec::new();
+        if width <= 0.0 || height <= 0.0 {
+            return tiles;
+        }
+
+        let (left, top) = Self::tile_coord(x, y);
+        let (right, bottom) = (Self::last_tile(x + width), Self::last_tile(y + height));
+        for tile_y in top..=bottom {
+            for tile_x in left..=right {
+                if self.attr_at(tile_x, tile_y) & mask != 0 {
+                    tiles.push((tile_x, tile_y));
+                }
+            }
+        }
+
+        tiles
+    }
+
+    pub fn collide_rect(&self, x: f64, y: f64, width: f64, height: f64, mask: u32) -> bool {
+        !self.rect_tiles(x, y, width, height, mask).is_empty()
+    }
+
+    pub fn sweep_rect(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        dx: f64,
+        dy: f64,
+        mask: u32,
+    ) -> SweepResult {
+        // Axes are resolved one at a time so that rects slide along walls
+        let (x, hit_x) = self.sweep_axis(x, y, width, height, dx, mask, true);
+        let (y, hit_y) = self.sweep_axis(y, x, height, width, dy, mask, false);
+        SweepResult { x, y, hit_x, hit_y }
+    }
+
+    pub fn raycast(&self, x1: f64, y1: f64, x2: f64, y2: f64, mask: u32) -> Option<RaycastHit> {
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let length = dx.hypot(dy);
+        let (mut tile_x, mut tile_y) = Self::tile_coord(x1, y1);
+        let (end_x, end_y) = Self::tile_coord(x2, y2);
+        let step_x = if dx > 0.0 { 1 } else { -1 };
+        let step_y = if dy > 0.0 { 1 } else { -1 };
+
+        // Distances along the ray are measured as fractions of its length
+        let boundary_t = |pos: f64, delta: f64, tile: i32| {
+            if delta == 0.0 {
+                f64::INFINITY
+            } else {
+                let edge = if delta > 0.0 { tile + 1 } else { tile } as f64 * TILE;
+                (edge - pos) / delta
+            }
+        };
+        let delta_t_x = if dx == 0.0 {
+            f64::INFINITY
+        } else {
+            TILE / dx.abs()
+        };
+        let delta_t_y = if dy == 0.0 {
+            f64::INFINITY
+        } else {
+            TILE / dy.abs()
+        };
+        let mut t_x = boundary_t(x1, dx, tile_x);
+        let mut t_y = boundary_t(y1, dy, tile_y);
+        let (mut x, mut y) = (x1, y1);
+
+        loop {
+            if self.attr_at(tile_x, tile_y) & mask != 0 {
+                return Some(RaycastHit {
+                    x,
+                    y,
+                    tile_x,
+                    tile_y,
+                });
+            }
+            if (tile_x == end_x && tile_y == end_y) || length == 0.0 {
+                return None;
+            }
+
+            // The crossed edge is snapped to avoid rounding errors along the ray
+            let t = if t_x < t_y {
+                let t = t_x;
+                t_x += delta_t_x;
+                x = if dx > 0.0 { tile_x + 1 } else { tile_x } as f64 * TILE;
+                y = y1 + dy * t;
+                tile_x += step_x;
+                t
+            } else {
+                let t = t_y;
+                t_y += delta_t_y;
+                x = x1 + dx * t;
+                y = if dy > 0.0 { tile_y + 1 } else { tile_y } as f64 * TILE;
+                tile_y += step_y;
+                t
+            };
+            if t > 1.0 {
+                return None;
+            }
+        }
+    }
+
+    fn sweep_axis(
+        &self,
+        pos: f64,
+        cross_pos: f64,
+        size: f64,
+        cross_size: f64,
+        delta: f64,
+        mask: u32,
+        is_x_axis: bool,
+    ) -> (f64, bool) {
+        if delta == 0.0 {
+            return (pos, false);
+        }
+
+        let cross_start = (cross_pos / TILE).floor() as i32;
+        let cross_end = Self::last_tile(cross_pos + cross_size);
+        let is_solid = |tile: i32| {
+            (cross_start..=cross_end).any(|cross_tile| {
+                let attr = if is_x_axis {
+                    self.attr_at(tile, cross_tile)
+                } else {
+                    self.attr_at(cross_tile, tile)
+                };
+                attr & mask != 0
+            })
+        };
+
+        if delta > 0.0 {
+            let first = ((pos + size) / TILE).floor() as i32;
+            let last = Self::last_tile(pos + size + delta);
+            for tile in first..=last {
+                if is_solid(tile) {
+                    return ((tile as f64 * TILE - size).max(pos), true);
+                }
+            }
+        } else {
+            let first = Self::last_tile(pos);
+            let last = ((pos + delta) / TILE).floor() as i32;
+            for tile in (last..=first).rev() {
+                if is_solid(tile) {
+                    return (((tile + 1) as f64 * TILE).min(pos), true);
+                }
+            }
+        }
+
+        (pos + delta, false)
+    }
+
+    fn tile_coord(x: f64, y: f64) -> (i32, i32) {
+        ((x / TILE).floor() as i32, (y / TILE).floor() as i32)
+    }
+
+    // Tile containing the pixel just before an exclusive edge
+    fn last_tile(edge: f64) -> i32 {
+        (edge / TILE).ceil() as i32 - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::{ImageSource, SharedTilemap};
+
+    const SOLID: u32 = 1;
+
+    fn wall_tilemap() -> SharedTilemap {
+        let tilemap = Tilemap::new(8, 8, ImageSource::Index(0));
+        {
+            let mut tilemap = tilemap.lock();
+            tilemap.set_attr((1, 0), SOLID);
+            tilemap.rect(4.0, 0.0, 1.0, 8.0, (1, 0));
+        }
+        tilemap
+    }
+
+    #[test]
+    fn test_rect_tiles() {
+        let tilemap = wall_tilemap();
+        let tilemap = tilemap.lock();
+        assert_eq!(
+            tilemap.rect_tiles(24.0, 0.0, 16.0, 8.0, SOLID),
+            vec![(4, 0)]
+        );
+        assert!(!tilemap.collide_rect(24.0, 0.0, 8.0, 8.0, SOLID));
+        assert!(tilemap.collide_rect(24.0, 0.0, 8.1, 8.0, SOLID));
+    }
+
+    #[test]
+    fn test_sweep_rect() {
+        let tilemap = wall_tilemap();
+        let tilemap = tilemap.lock();
+        let result = tilemap.sweep_rect(10.0, 10.0, 8.0, 8.0, 30.0, 5.0, SOLID);
+        assert_eq!(
+            result,
+            SweepResult {
+                x: 24.0,
+                y: 15.0,
+                hit_x: true,
+                hit_y: false,
+            }
+        );
+
+        let result = tilemap.sweep_rect(50.0, 10.0, 8.0, 8.0, -30.0, 0.0, SOLID);
+        assert_eq!((result.x, result.hit_x), (40.0, true));
+    }
+
+    #[test]
+    fn test_raycast() {
+        let tilemap = wall_tilemap();
+        let tilemap = tilemap.lock();
+        let hit = tilemap.raycast(4.0, 4.0, 60.0, 4.0, SOLID).unwrap();
+        assert_eq!((hit.x, hit.tile_x, hit.tile_y), (32.0, 4, 0));
+        assert!(tilemap.raycast(4.0, 4.0, 20.0, 20.0, SOLID).is_none());
+    }
+}
@@ -1,5 +1,8 @@
+use std::collections::HashMap;
+
 use crate::canvas::{Canvas, ToIndex};
 use crate::image::SharedImage;
+use crate::tile_animation::TileAnimation;
 use crate::utils::{f64_to_u32, parse_hex_string, simplify_string};
 
 pub type ImageTileCoord = u8;
@@ -20,6 +23,8 @@ pub enum ImageSource {
 pub struct Tilemap {
     pub(crate) canvas: Canvas<Tile>,
     pub imgsrc: ImageSource,
+    pub attributes: HashMap<Tile, u32>,
+    pub animations: HashMap<Tile, TileAnimation>,
 }
 
 pub type SharedTilemap = shared_type!(Tilemap);
@@ -29,6 +34,8 @@ impl Tilemap {
         new_shared_type!(Self {
             canvas: Canvas::new(width, height),
             imgsrc,
+            attributes: HashMap::new(),
+            animations: HashMap::new(),
         })
     }
 
@@ -86,6 +93,7 @@ impl Tilemap {
         let tilemap = Self::from_tmx(filename, layer_index);
         let tilemap_width = tilemap.lock().width();
         let tilemap_height = tilemap.lock().height();
+        self.animations.extend(tilemap.lock().animations.clone());
 
         self.blt(
             x as f64,
@@ -101,6 +109,25 @@ impl Tilemap {
         );
     }
 
+    pub fn set_attr(&mut self, tile: Tile, flags: u32) {
+        if flags == 0 {
+            self.attributes.remove(&tile);
+        } else {
+            self.attributes.insert(tile, flags);
+        }
+    }
+
+    pub fn attr(&self, tile: Tile) -> u32 {
+        self.attributes.get(&tile).copied().unwrap_or(0)
+    }
+
+    pub fn attr_at(&self, x: i32, y: i32) -> u32 {
+        if x < 0 || y < 0 || x >= self.width() as i32 || y >= self.height() as i32 {
+            return 0;
+        }
+        self.attr(self.canvas.read_data(x as usize, y as usize))
+    }
+
     pub fn clip(&mut self, x: f64, y: f64, width: f64, height: f64) {
         self.canvas.clip(x, y, width, height);
     }
@@ -1,17 +1,40 @@
 use std::fs::File;
 use std::io::Read;
 
+use std::sync::atomic::Ordering;
+
 use serde::Deserialize;
 
+use crate::pyxel::FPS;
 use crate::settings::TILE_SIZE;
 use crate::tilemap::{ImageSource, Tilemap};
 use crate::utils::remove_whitespace;
 use crate::SharedTilemap;
 
+#[derive(Debug, Deserialize)]
+struct AnimationFrame {
+    tileid: u32,
+    duration: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Animation {
+    #[serde(rename = "frame", default)]
+    frames: Vec<AnimationFrame>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TilesetTile {
+    id: u32,
+    animation: Option<Animation>,
+}
+
 #[derive(Debug, Deserialize)]
 struct Tileset {
     firstgid: u32,
     columns: Option<u32>,
+    #[serde(rename = "tile", default)]
+    tiles: Vec<TilesetTile>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -94,9 +117,33 @@ impl Tilemap {
                 .map(|s| s.parse::<u32>().unwrap())
                 .collect();
 
+            let tile_coord = |tile_id: u32| {
+                (
+                    (tile_id % tileset_columns) as u8,
+                    (tile_id / tileset_columns) as u8,
+                )
+            };
+
             let tilemap = Self::new(layer.width, layer.height, ImageSource::Index(0));
             {
                 let mut tilemap = tilemap.lock();
+
+                // Frame durations are converted from milliseconds to frames
+                let fps = FPS.load(Ordering::Relaxed);
+                for tile in &tileset.tiles {
+                    if let Some(animation) = &tile.animation {
+                        let frames: Vec<_> = animation
+                            .frames
+                            .iter()
+                            .map(|frame| {
+                                let duration = (frame.duration * fps).div_ceil(1000);
+                                (tile_coord(frame.tileid), duration.max(1))
+                            })
+                            .collect();
+                        tilemap.set_animation(tile_coord(tile.id), &frames);
+                    }
+                }
+
                 for (i, tile_id) in layer_data.iter().enumerate() {
                     let x = i % layer.width as usize;
                     let y = i / layer.width as usize;
@@ -105,9 +152,7 @@ impl Tilemap {
                     } else {
                         0
                     };
-                    let tile_x = (tile_id % tileset_columns) as u8;
-                    let tile_y = (tile_id / tileset_columns) as u8;
-                    tilemap.canvas.write_data(x, y, (tile_x, tile_y));
+                    tilemap.canvas.write_data(x, y, tile_coord(tile_id));
                 }
             }
 
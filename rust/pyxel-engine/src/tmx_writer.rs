@@ -0,0 +1,172 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+
+use crate::pyxel::{FPS, IMAGES};
+use crate::settings::TILE_SIZE;
+use crate::tilemap::{ImageSource, Tile, Tilemap};
+use crate::utils;
+
+const FIRST_GID: u32 = 1;
+
+impl Tilemap {
+    pub fn save_tmx(&self, filename: &str, tileset_filename: Option<&str>) -> Result<(), String> {
+        let filename = utils::add_file_extension(filename, ".tmx");
+        let path = Path::new(&filename);
+        let stem = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().to_string());
+        let tileset_filename = tileset_filename.map_or_else(
+            || format!("{stem}_tileset.png"),
+            |tileset_filename| utils::add_file_extension(tileset_filename, ".png"),
+        );
+
+        // The tileset image is written next to the map and referenced relatively
+        let image = match &self.imgsrc {
+            ImageSource::Index(index) => IMAGES.lock()[*index as usize].clone(),
+            ImageSource::Image(image) => image.clone(),
+        };
+        let (image_width, image_height) = {
+            let image = image.lock();
+            let image_path = path.with_file_name(&tileset_filename);
+            image.save(&image_path.to_string_lossy(), 1, Some(true), None, None);
+            (image.width(), image.height())
+        };
+        let columns = image_width / TILE_SIZE;
+        let tile_count = columns * (image_height / TILE_SIZE);
+
+        let mut tmx = String::new();
+        let _ = writeln!(tmx, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            tmx,
+            r#"<map version="1.10" orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{TILE_SIZE}" tileheight="{TILE_SIZE}" infinite="0" nextlayerid="2" nextobjectid="1">"#,
+            self.width(),
+            self.height()
+        );
+        let _ = writeln!(
+            tmx,
+            r#" <tileset firstgid="{FIRST_GID}" name="{}" tilewidth="{TILE_SIZE}" tileheight="{TILE_SIZE}" tilecount="{tile_count}" columns="{columns}">"#,
+            escape_xml(&stem)
+        );
+        let _ = writeln!(
+            tmx,
+            r#"  <image source="{}" width="{image_width}" height="{image_height}"/>"#,
+            escape_xml(&tileset_filename)
+        );
+
+        // Animation durations are converted from frames to milliseconds
+        let fps = FPS.load(Ordering::Relaxed);
+        let mut animations: Vec<_> = self.animations.iter().collect();
+        animations.sort_by_key(|(tile, _)| Self::tile_id(**tile, columns));
+        for (tile, animation) in animations {
+            let _ = writeln!(tmx, r#"  <tile id="{}">"#, Self::tile_id(*tile, columns));
+            let _ = writeln!(tmx, "   <animation>");
+            for &(frame_tile, duration) in &animation.frames {
+                let _ = writeln!(
+                    tmx,
+                    r#"    <frame tileid="{}" duration="{}"/>"#,
+                    Self::tile_id(frame_tile, columns),
+                    duration * 1000 / fps
+                );
+            }
+            let _ = writeln!(tmx, "   </animation>");
+            let _ = writeln!(tmx, "  </tile>");
+        }
+        let _ = writeln!(tmx, " </tileset>");
+
+        let _ = writeln!(
+            tmx,
+            r#" <layer id="1" name="Tile Layer 1" width="{}" height="{}">"#,
+            self.width(),
+            self.height()
+        );
+        let _ = writeln!(tmx, r#"  <data encoding="csv">"#);
+        tmx += &self.gid_csv(columns, true);
+        let _ = writeln!(tmx, "</data>");
+        let _ = writeln!(tmx, " </layer>");
+        let _ = writeln!(tmx, "</map>");
+
+        fs::write(path, tmx).map_err(|_| format!("Failed to open file '{filename}'"))
+    }
+
+    pub fn save_csv(&self, filename: &str) -> Result<(), String> {
+        let filename = utils::add_file_extension(filename, ".csv");
+        let image_width = match &self.imgsrc {
+            ImageSource::Index(index) => IMAGES.lock()[*index as usize].lock().width(),
+            ImageSource::Image(image) => image.lock().width(),
+        };
+        fs::write(&filename, self.gid_csv(image_width / TILE_SIZE, false))
+            .map_err(|_| format!("Failed to open file '{filename}'"))
+    }
+
+    fn tile_id(tile: Tile, columns: u32) -> u32 {
+        tile.1 as u32 * columns + tile.0 as u32
+    }
+
+    fn gid_csv(&self, columns: u32, is_tmx: bool) -> String {
+        let mut csv = String::new();
+        for y in 0..self.height() {
+            let row: Vec<String> = (0..self.width())
+                .map(|x| {
+                    let tile = self.canvas.read_data(x as usize, y as usize);
+                    (FIRST_GID + Self::tile_id(tile, columns)).to_string()
+                })
+                .collect();
+            csv += &row.join(",");
+
+            // Tiled separates rows with a trailing comma inside TMX data
+            if is_tmx && y + 1 < self.height() {
+                csv.push(',');
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Image;
+
+    #[test]
+    fn test_save_tmx() {
+        let dir = std::env::temp_dir();
+        let filename = dir.join("pyxel_tmx_writer_test.tmx");
+        let filename = filename.to_str().unwrap();
+        let tileset_filename = dir.join("pyxel_tmx_writer_test_tileset.png");
+
+        let tilemap = Tilemap::new(3, 2, ImageSource::Image(Image::new(32, 16)));
+        {
+            let mut tilemap = tilemap.lock();
+            tilemap.pset(1.0, 0.0, (3, 1));
+            tilemap.pset(2.0, 1.0, (2, 0));
+            tilemap.set_animation((2, 0), &[((2, 0), 15), ((3, 0), 15)]);
+            tilemap.save_tmx(filename, None).unwrap();
+        }
+        assert!(tileset_filename.exists());
+
+        let tmx = fs::read_to_string(filename).unwrap();
+        assert!(tmx.contains(r#"columns="4""#));
+        assert!(tmx.contains("1,8,1,\n1,1,3\n"));
+        assert!(tmx.contains(r#"<frame tileid="3" duration="500"/>"#));
+        assert_eq!(escape_xml(r#"a&b"<c>'"#), "a&amp;b&quot;&lt;c&gt;&apos;");
+
+        let loaded = Tilemap::from_tmx(filename, 0);
+        fs::remove_file(filename).unwrap();
+        fs::remove_file(tileset_filename).unwrap();
+        let mut loaded = loaded.lock();
+        assert_eq!(loaded.pget(1.0, 0.0), (3, 1));
+        assert_eq!(loaded.pget(2.0, 1.0), (2, 0));
+        assert_eq!(loaded.animated_tile((2, 0), 20), (3, 0));
+    }
+}
@@ -0,0 +1,103 @@
+use pyo3::prelude::*;
+
+use crate::pyxel_singleton::pyxel;
+use crate::tilemap_wrapper::Tilemap;
+
+#[pyclass]
+#[derive(Clone)]
+pub struct ViewCamera;
+
+// The view camera lives in the Pyxel singleton, so the methods do not use self
+#[allow(clippy::unused_self)]
+#[pymethods]
+impl ViewCamera {
+    #[getter]
+    pub fn enabled(&self) -> bool {
+        pyxel().view_camera.enabled
+    }
+
+    #[getter]
+    pub fn x(&self) -> f64 {
+        pyxel().view_camera.x
+    }
+
+    #[getter]
+    pub fn y(&self) -> f64 {
+        pyxel().view_camera.y
+    }
+
+    #[getter]
+    pub fn zoom(&self) -> f64 {
+        pyxel().view_camera.zoom
+    }
+
+    #[setter]
+    pub fn set_zoom(&self, zoom: f64) {
+        pyxel().view_camera.zoom = zoom;
+    }
+
+    #[getter]
+    pub fn smoothing(&self) -> f64 {
+        pyxel().view_camera.smoothing
+    }
+
+    #[setter]
+    pub fn set_smoothing(&self, smoothing: f64) {
+        pyxel().view_camera.smoothing = smoothing;
+    }
+
+    pub fn follow(&self, x: f64, y: f64) {
+        pyxel().view_camera.follow(x, y);
+    }
+
+    pub fn unfollow(&self) {
+        pyxel().view_camera.unfollow();
+    }
+
+    pub fn look_at(&self, x: f64, y: f64) {
+        pyxel().view_camera.look_at(x, y);
+    }
+
+    pub fn disable(&self) {
+        pyxel().disable_view_camera();
+    }
+
+    pub fn set_deadzone(&self, w: f64, h: f64) {
+        pyxel().view_camera.set_deadzone(w, h);
+    }
+
+    pub fn set_bounds(&self, x: f64, y: f64, w: f64, h: f64) {
+        pyxel().view_camera.set_bounds(x, y, w, h);
+    }
+
+    pub fn clamp_to_tilemap(&self, tm: Bound<'_, PyAny>) -> PyResult<()> {
+        let tilemap = cast_pyany! {
+            tm,
+            (u32, { pyxel().tilemaps.lock()[tm as usize].clone() }),
+            (Tilemap, { tm.inner })
+        };
+        pyxel().view_camera.clamp_to_tilemap(&tilemap.lock());
+        Ok(())
+    }
+
+    #[pyo3(signature = (intensity, decay=None))]
+    pub fn shake(&self, intensity: f64, decay: Option<f64>) {
+        pyxel().view_camera.shake(intensity, decay);
+    }
+
+    pub fn apply_zoom(&self) {
+        pyxel().zoom_view_camera();
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (tm, colkey=None))]
+fn bltm_camera(tm: u32, colkey: Option<pyxel::Color>) {
+    pyxel().bltm_camera(tm, colkey);
+}
+
+pub fn add_camera_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<ViewCamera>()?;
+    m.add_function(wrap_pyfunction!(bltm_camera, m)?)?;
+    Ok(())
+}
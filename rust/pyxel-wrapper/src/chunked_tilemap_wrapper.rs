@@ -0,0 +1,214 @@
+use pyo3::exceptions::PyIOError;
+use pyo3::prelude::*;
+
+use crate::image_wrapper::Image;
+use crate::pyxel_singleton::pyxel;
+use crate::tilemap_wrapper::Tilemap;
+
+#[pyclass]
+#[derive(Clone)]
+pub struct ChunkedTilemap {
+    pub(crate) inner: pyxel::SharedChunkedTilemap,
+}
+
+impl ChunkedTilemap {
+    pub fn wrap(inner: pyxel::SharedChunkedTilemap) -> Self {
+        Self { inner }
+    }
+}
+
+#[pymethods]
+impl ChunkedTilemap {
+    #[new]
+    pub fn new(width: u32, height: u32, img: Bound<'_, PyAny>) -> PyResult<Self> {
+        let imgsrc = cast_pyany! {
+            img,
+            (u32, { pyxel::ImageSource::Index(img) }),
+            (Image, { pyxel::ImageSource::Image(img.inner) })
+        };
+        Ok(Self::wrap(pyxel::ChunkedTilemap::new(
+            width, height, imgsrc,
+        )))
+    }
+
+    #[getter]
+    pub fn width(&self) -> u32 {
+        self.inner.lock().width()
+    }
+
+    #[getter]
+    pub fn height(&self) -> u32 {
+        self.inner.lock().height()
+    }
+
+    #[getter]
+    pub fn imgsrc(&self, py: Python) -> PyObject {
+        let tilemap = self.inner.lock();
+        match &tilemap.imgsrc {
+            pyxel::ImageSource::Index(index) => value_to_pyobj!(py, index),
+            pyxel::ImageSource::Image(image) => class_to_pyobj!(py, Image::wrap(image.clone())),
+        }
+    }
+
+    #[setter]
+    pub fn set_imgsrc(&self, img: Bound<'_, PyAny>) -> PyResult<()> {
+        let imgsrc = cast_pyany! {
+            img,
+            (u32, { pyxel::ImageSource::Index(img) }),
+            (Image, { pyxel::ImageSource::Image(img.inner) })
+        };
+        self.inner.lock().imgsrc = imgsrc;
+        Ok(())
+    }
+
+    pub fn chunk_coords(&self) -> Vec<pyxel::ChunkCoord> {
+        self.inner.lock().chunk_coords()
+    }
+
+    pub fn is_chunk_loaded(&self, chunk_x: i32, chunk_y: i32) -> bool {
+        self.inner.lock().is_chunk_loaded(chunk_x, chunk_y)
+    }
+
+    pub fn load_chunk(&self, chunk_x: i32, chunk_y: i32, filename: &str) -> PyResult<()> {
+        self.inner
+            .lock()
+            .load_chunk(chunk_x, chunk_y, filename)
+            .map_err(PyIOError::new_err)
+    }
+
+    pub fn save_chunk(&self, chunk_x: i32, chunk_y: i32, filename: &str) -> PyResult<()> {
+        self.inner
+            .lock()
+            .save_chunk(chunk_x, chunk_y, filename)
+            .map_err(PyIOError::new_err)
+    }
+
+    pub fn unload_chunk(&self, chunk_x: i32, chunk_y: i32) {
+        self.inner.lock().unload_chunk(chunk_x, chunk_y);
+    }
+
+    pub fn region(&self, x: i32, y: i32, w: u32, h: u32) -> Tilemap {
+        Tilemap::wrap(self.inner.lock().region(x, y, w, h))
+    }
+
+    pub fn set_animation(&self, tile: pyxel::Tile, frames: Vec<(pyxel::Tile, u32)>) {
+        let mut tilemap = self.inner.lock();
+        if frames.is_empty() {
+            tilemap.animations.remove(&tile);
+        } else {
+            tilemap
+                .animations
+                .insert(tile, pyxel::TileAnimation::new(&frames));
+        }
+    }
+
+    #[pyo3(signature = (x=None, y=None, w=None, h=None))]
+    pub fn clip(
+        &self,
+        x: Option<f64>,
+        y: Option<f64>,
+        w: Option<f64>,
+        h: Option<f64>,
+    ) -> PyResult<()> {
+        if let (Some(x), Some(y), Some(w), Some(h)) = (x, y, w, h) {
+            self.inner.lock().clip(x, y, w, h);
+        } else if (x, y, w, h) == (None, None, None, None) {
+            self.inner.lock().clip0();
+        } else {
+            python_type_error!("clip() takes 0 or 4 arguments");
+        }
+        Ok(())
+    }
+
+    #[pyo3(signature = (x=None, y=None))]
+    pub fn camera(&self, x: Option<f64>, y: Option<f64>) -> PyResult<()> {
+        if let (Some(x), Some(y)) = (x, y) {
+            self.inner.lock().camera(x, y);
+        } else if (x, y) == (None, None) {
+            self.inner.lock().camera0();
+        } else {
+            python_type_error!("camera() takes 0 or 2 arguments");
+        }
+        Ok(())
+    }
+
+    pub fn cls(&self, tile: pyxel::Tile) {
+        self.inner.lock().cls(tile);
+    }
+
+    pub fn pget(&self, x: f64, y: f64) -> pyxel::Tile {
+        self.inner.lock().pget(x, y)
+    }
+
+    pub fn pset(&self, x: f64, y: f64, tile: pyxel::Tile) {
+        self.inner.lock().pset(x, y, tile);
+    }
+
+    pub fn line(&self, x1: f64, y1: f64, x2: f64, y2: f64, tile: pyxel::Tile) {
+        self.inner.lock().line(x1, y1, x2, y2, tile);
+    }
+
+    pub fn rect(&self, x: f64, y: f64, w: f64, h: f64, tile: pyxel::Tile) {
+        self.inner.lock().rect(x, y, w, h, tile);
+    }
+
+    pub fn rectb(&self, x: f64, y: f64, w: f64, h: f64, tile: pyxel::Tile) {
+        self.inner.lock().rectb(x, y, w, h, tile);
+    }
+
+    pub fn circ(&self, x: f64, y: f64, r: f64, tile: pyxel::Tile) {
+        self.inner.lock().circ(x, y, r, tile);
+    }
+
+    pub fn circb(&self, x: f64, y: f64, r: f64, tile: pyxel::Tile) {
+        self.inner.lock().circb(x, y, r, tile);
+    }
+
+    pub fn elli(&self, x: f64, y: f64, w: f64, h: f64, tile: pyxel::Tile) {
+        self.inner.lock().elli(x, y, w, h, tile);
+    }
+
+    pub fn ellib(&self, x: f64, y: f64, w: f64, h: f64, tile: pyxel::Tile) {
+        self.inner.lock().ellib(x, y, w, h, tile);
+    }
+
+    pub fn tri(&self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: pyxel::Tile) {
+        self.inner.lock().tri(x1, y1, x2, y2, x3, y3, tile);
+    }
+
+    pub fn trib(&self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: pyxel::Tile) {
+        self.inner.lock().trib(x1, y1, x2, y2, x3, y3, tile);
+    }
+
+    pub fn fill(&self, x: f64, y: f64, tile: pyxel::Tile) {
+        self.inner.lock().fill(x, y, tile);
+    }
+
+    #[pyo3(signature = (x, y, tm, u, v, w, h, tilekey=None))]
+    pub fn blt(
+        &self,
+        x: f64,
+        y: f64,
+        tm: Bound<'_, PyAny>,
+        u: f64,
+        v: f64,
+        w: f64,
+        h: f64,
+        tilekey: Option<pyxel::Tile>,
+    ) -> PyResult<()> {
+        cast_pyany! {
+            tm,
+            (u32, {
+                let tilemap = pyxel().tilemaps.lock()[tm as usize].clone();
+                self.inner.lock().blt(x, y, tilemap, u, v, w, h, tilekey);
+            }),
+            (Tilemap, { self.inner.lock().blt(x, y, tm.inner, u, v, w, h, tilekey); })
+        }
+        Ok(())
+    }
+}
+
+pub fn add_chunked_tilemap_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<ChunkedTilemap>()?;
+    Ok(())
+}
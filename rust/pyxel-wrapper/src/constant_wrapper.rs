@@ -42,6 +42,26 @@ pub fn add_module_constants(m: &Bound<'_, PyModule>) -> PyResult<()> {
     add_constant!(COLOR_PEACH)?;
     add_constant!(FONT_WIDTH)?;
     add_constant!(FONT_HEIGHT)?;
+    add_constant!(ALIGN_LEFT)?;
+    add_constant!(ALIGN_CENTER)?;
+    add_constant!(ALIGN_RIGHT)?;
+    add_constant!(STENCIL_OFF)?;
+    add_constant!(STENCIL_TEST)?;
+    add_constant!(STENCIL_WRITE)?;
+    add_constant!(DITHER_NONE)?;
+    add_constant!(DITHER_ORDERED)?;
+    add_constant!(DITHER_FLOYD_STEINBERG)?;
+    add_constant!(QUANTIZE_MEDIAN_CUT)?;
+    add_constant!(QUANTIZE_K_MEANS)?;
+    add_constant!(UPSCALE_NONE)?;
+    add_constant!(UPSCALE_SCALE2X)?;
+    add_constant!(UPSCALE_SCALE3X)?;
+    add_constant!(UPSCALE_EPX)?;
+    add_constant!(AUTOTILE_BLOB16)?;
+    add_constant!(AUTOTILE_BLOB47)?;
+    add_constant!(LOOP_ONCE)?;
+    add_constant!(LOOP_REPEAT)?;
+    add_constant!(LOOP_PING_PONG)?;
 
     add_constant!(NUM_CHANNELS)?;
     add_constant!(NUM_TONES)?;
@@ -0,0 +1,189 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Emitter {
+    pub(crate) inner: pyxel::SharedEmitter,
+}
+
+impl Emitter {
+    pub fn wrap(inner: pyxel::SharedEmitter) -> Self {
+        Self { inner }
+    }
+}
+
+#[pymethods]
+impl Emitter {
+    #[new]
+    pub fn new(x: f64, y: f64) -> Self {
+        Self::wrap(pyxel::Emitter::new(x, y))
+    }
+
+    #[getter]
+    pub fn x(&self) -> f64 {
+        self.inner.lock().x
+    }
+
+    #[setter]
+    pub fn set_x(&self, x: f64) {
+        self.inner.lock().x = x;
+    }
+
+    #[getter]
+    pub fn y(&self) -> f64 {
+        self.inner.lock().y
+    }
+
+    #[setter]
+    pub fn set_y(&self, y: f64) {
+        self.inner.lock().y = y;
+    }
+
+    #[getter]
+    pub fn active(&self) -> bool {
+        self.inner.lock().active
+    }
+
+    #[setter]
+    pub fn set_active(&self, active: bool) {
+        self.inner.lock().active = active;
+    }
+
+    #[getter]
+    pub fn spawn_rate(&self) -> f64 {
+        self.inner.lock().spawn_rate
+    }
+
+    #[setter]
+    pub fn set_spawn_rate(&self, spawn_rate: f64) {
+        self.inner.lock().spawn_rate = spawn_rate;
+    }
+
+    #[getter]
+    pub fn max_particles(&self) -> usize {
+        self.inner.lock().max_particles
+    }
+
+    #[setter]
+    pub fn set_max_particles(&self, max_particles: usize) {
+        self.inner.lock().max_particles = max_particles;
+    }
+
+    #[getter]
+    pub fn lifetime(&self) -> (u32, u32) {
+        self.inner.lock().lifetime
+    }
+
+    #[setter]
+    pub fn set_lifetime(&self, lifetime: (u32, u32)) {
+        self.inner.lock().lifetime = lifetime;
+    }
+
+    #[getter]
+    pub fn speed(&self) -> (f64, f64) {
+        self.inner.lock().speed
+    }
+
+    #[setter]
+    pub fn set_speed(&self, speed: (f64, f64)) {
+        self.inner.lock().speed = speed;
+    }
+
+    #[getter]
+    pub fn angle(&self) -> (f64, f64) {
+        self.inner.lock().angle
+    }
+
+    #[setter]
+    pub fn set_angle(&self, angle: (f64, f64)) {
+        self.inner.lock().angle = angle;
+    }
+
+    #[getter]
+    pub fn gravity(&self) -> (f64, f64) {
+        self.inner.lock().gravity
+    }
+
+    #[setter]
+    pub fn set_gravity(&self, gravity: (f64, f64)) {
+        self.inner.lock().gravity = gravity;
+    }
+
+    #[getter]
+    pub fn drag(&self) -> f64 {
+        self.inner.lock().drag
+    }
+
+    #[setter]
+    pub fn set_drag(&self, drag: f64) {
+        self.inner.lock().drag = drag;
+    }
+
+    #[getter]
+    pub fn colors(&self) -> Vec<pyxel::Color> {
+        self.inner.lock().colors.clone()
+    }
+
+    #[setter]
+    pub fn set_colors(&self, colors: Vec<pyxel::Color>) {
+        self.inner.lock().colors = colors;
+    }
+
+    #[getter]
+    pub fn colkey(&self) -> Option<pyxel::Color> {
+        self.inner.lock().color_key
+    }
+
+    #[setter]
+    pub fn set_colkey(&self, colkey: Option<pyxel::Color>) {
+        self.inner.lock().color_key = colkey;
+    }
+
+    #[getter]
+    pub fn num_particles(&self) -> usize {
+        self.inner.lock().particles.len()
+    }
+
+    pub fn shape_pixel(&self) -> PyResult<()> {
+        self.inner
+            .lock()
+            .set_shape(pyxel::ParticleShape::Pixel)
+            .map_err(PyValueError::new_err)
+    }
+
+    pub fn shape_circle(&self, radius: f64) -> PyResult<()> {
+        self.inner
+            .lock()
+            .set_shape(pyxel::ParticleShape::Circle(radius))
+            .map_err(PyValueError::new_err)
+    }
+
+    pub fn shape_sprite(&self, img: u32, u: f64, v: f64, w: f64, h: f64) -> PyResult<()> {
+        self.inner
+            .lock()
+            .set_shape(pyxel::ParticleShape::Sprite(img, u, v, w, h))
+            .map_err(PyValueError::new_err)
+    }
+
+    pub fn seed(&self, seed: u32) {
+        self.inner.lock().seed(seed);
+    }
+
+    pub fn burst(&self, count: u32) {
+        self.inner.lock().burst(count);
+    }
+
+    pub fn update(&self) {
+        self.inner.lock().update();
+    }
+
+    pub fn clear(&self) {
+        self.inner.lock().clear();
+    }
+}
+
+pub fn add_emitter_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Emitter>()?;
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use pyo3::exceptions::{PyIOError, PyValueError};
 use pyo3::prelude::*;
 
 #[pyclass]
@@ -15,13 +16,28 @@ impl Font {
 #[pymethods]
 impl Font {
     #[new]
-    pub fn new(filename: &str) -> Self {
-        Self::wrap(pyxel::Font::new(filename))
+    #[pyo3(signature = (filename, font_size=None))]
+    pub fn new(filename: &str, font_size: Option<f64>) -> PyResult<Self> {
+        pyxel::Font::new(filename, font_size)
+            .map(Self::wrap)
+            .map_err(PyIOError::new_err)
     }
 
     pub fn text_width(&self, s: &str) -> i32 {
         self.inner.lock().text_width(s)
     }
+
+    pub fn set_fallbacks(&self, fonts: Vec<Font>) -> PyResult<()> {
+        let fonts = fonts.into_iter().map(|font| font.inner).collect();
+        self.inner
+            .lock()
+            .set_fallbacks(fonts)
+            .map_err(PyValueError::new_err)
+    }
+
+    pub fn set_kerning(&self, left: char, right: char, offset: i32) {
+        self.inner.lock().set_kerning(left, right, offset);
+    }
 }
 
 pub fn add_font_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -1,10 +1,15 @@
 use std::sync::Once;
 
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
+use crate::chunked_tilemap_wrapper::ChunkedTilemap;
+use crate::emitter_wrapper::Emitter;
 use crate::font_wrapper::Font;
 use crate::image_wrapper::Image;
+use crate::layer_wrapper::Layer;
 use crate::pyxel_singleton::pyxel;
+use crate::sprite_wrapper::Sprite;
 use crate::tilemap_wrapper::Tilemap;
 
 static IMAGE_ONCE: Once = Once::new();
@@ -49,11 +54,67 @@ fn pal(col1: Option<pyxel::Color>, col2: Option<pyxel::Color>) -> PyResult<()> {
     Ok(())
 }
 
+#[pyfunction]
+#[pyo3(signature = (start=None, end=None, interval=None, reverse=None))]
+fn pal_cycle(
+    start: Option<pyxel::Color>,
+    end: Option<pyxel::Color>,
+    interval: Option<u32>,
+    reverse: Option<bool>,
+) -> PyResult<()> {
+    if let (Some(start), Some(end), Some(interval)) = (start, end, interval) {
+        pyxel().pal_cycle(start, end, interval, reverse);
+    } else if (start, end, interval, reverse) == (None, None, None, None) {
+        pyxel().pal_cycle0();
+    } else {
+        python_type_error!("pal_cycle() takes 0, 3 or 4 arguments");
+    }
+    Ok(())
+}
+
 #[pyfunction]
 fn dither(alpha: f32) {
     pyxel().dither(alpha);
 }
 
+#[pyfunction]
+#[pyo3(signature = (img=None, colkey=None))]
+fn stencil(img: Option<Bound<'_, PyAny>>, colkey: Option<pyxel::Color>) -> PyResult<()> {
+    let Some(img) = img else {
+        pyxel().stencil0();
+        return Ok(());
+    };
+    cast_pyany! {
+        img,
+        (u32, {
+            let image = pyxel().images.lock()[img as usize].clone();
+            pyxel().stencil(image, colkey);
+        }),
+        (Image, { pyxel().stencil(img.inner, colkey); })
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn stencil_mode(mode: u32) {
+    pyxel().stencil_mode(pyxel::StencilMode::from_index(mode));
+}
+
+#[pyfunction]
+fn stencil_set() {
+    pyxel().stencil_set();
+}
+
+#[pyfunction]
+fn stencil_clear() {
+    pyxel().stencil_clear();
+}
+
+#[pyfunction]
+fn stencil_invert() {
+    pyxel().stencil_invert();
+}
+
 #[pyfunction]
 fn cls(col: pyxel::Color) {
     pyxel().cls(col);
@@ -119,6 +180,16 @@ fn fill(x: f64, y: f64, col: pyxel::Color) {
     pyxel().fill(x, y, col);
 }
 
+#[pyfunction]
+fn linear_gradient(x: f64, y: f64, w: f64, h: f64, angle: f64, cols: Vec<pyxel::Color>) {
+    pyxel().linear_gradient(x, y, w, h, angle, &cols);
+}
+
+#[pyfunction]
+fn radial_gradient(x: f64, y: f64, r: f64, cols: Vec<pyxel::Color>) {
+    pyxel().radial_gradient(x, y, r, &cols);
+}
+
 #[pyfunction]
 #[pyo3(signature = (x, y ,img, u, v, w, h, colkey=None, rotate=None, scale=None))]
 fn blt(
@@ -142,7 +213,163 @@ fn blt(
 }
 
 #[pyfunction]
-#[pyo3(signature = (x, y, tm, u, v, w, h, colkey=None, rotate=None, scale=None))]
+#[pyo3(signature = (x, y, w, h, img, u, v, iw, ih, colkey=None, shear=None))]
+fn blt_stretch(
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    img: Bound<'_, PyAny>,
+    u: f64,
+    v: f64,
+    iw: f64,
+    ih: f64,
+    colkey: Option<pyxel::Color>,
+    shear: Option<f64>,
+) -> PyResult<()> {
+    cast_pyany! {
+        img,
+        (u32, { pyxel().blt_stretch(x, y, w, h, img, u, v, iw, ih, colkey, shear); }),
+        (Image, {
+            pyxel().screen.lock().blt_stretch(x, y, w, h, img.inner, u, v, iw, ih, colkey, shear);
+        })
+    }
+    Ok(())
+}
+
+#[pyfunction]
+#[pyo3(signature = (x, y, img, u, v, w, h, colkey, scale_x, scale_y, shear=None))]
+fn blt_scale(
+    x: f64,
+    y: f64,
+    img: Bound<'_, PyAny>,
+    u: f64,
+    v: f64,
+    w: f64,
+    h: f64,
+    colkey: Option<pyxel::Color>,
+    scale_x: f64,
+    scale_y: f64,
+    shear: Option<f64>,
+) -> PyResult<()> {
+    cast_pyany! {
+        img,
+        (u32, { pyxel().blt_scale(x, y, img, u, v, w, h, colkey, scale_x, scale_y, shear); }),
+        (Image, {
+            pyxel().screen.lock().blt_scale(x, y, img.inner, u, v, w, h, colkey, scale_x, scale_y, shear);
+        })
+    }
+    Ok(())
+}
+
+#[pyfunction]
+#[pyo3(signature = (x, y, w, h, img, u, v, tw, th, colkey=None))]
+fn blt_tiled(
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    img: Bound<'_, PyAny>,
+    u: f64,
+    v: f64,
+    tw: f64,
+    th: f64,
+    colkey: Option<pyxel::Color>,
+) -> PyResult<()> {
+    cast_pyany! {
+        img,
+        (u32, { pyxel().blt_tiled(x, y, w, h, img, u, v, tw, th, colkey); }),
+        (Image, { pyxel().screen.lock().blt_tiled(x, y, w, h, img.inner, u, v, tw, th, colkey); })
+    }
+    Ok(())
+}
+
+#[pyfunction]
+#[pyo3(signature = (x, y, w, h, img, u, v, iw, ih, left, top, right, bottom, colkey=None, tiled=None))]
+fn blt9(
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    img: Bound<'_, PyAny>,
+    u: f64,
+    v: f64,
+    iw: f64,
+    ih: f64,
+    left: f64,
+    top: f64,
+    right: f64,
+    bottom: f64,
+    colkey: Option<pyxel::Color>,
+    tiled: Option<bool>,
+) -> PyResult<()> {
+    cast_pyany! {
+        img,
+        (u32, {
+            pyxel().blt9(x, y, w, h, img, u, v, iw, ih, left, top, right, bottom, colkey, tiled);
+        }),
+        (Image, {
+            pyxel().screen.lock().blt9(
+                x, y, w, h, img.inner, u, v, iw, ih, left, top, right, bottom, colkey, tiled,
+            );
+        })
+    }
+    Ok(())
+}
+
+#[pyfunction]
+#[pyo3(signature = (x, y, name, colkey=None, rotate=None, scale=None))]
+fn blt_sprite(
+    x: f64,
+    y: f64,
+    name: &str,
+    colkey: Option<pyxel::Color>,
+    rotate: Option<f64>,
+    scale: Option<f64>,
+) -> PyResult<()> {
+    pyxel()
+        .blt_sprite(x, y, name, colkey, rotate, scale)
+        .map_err(PyValueError::new_err)
+}
+
+#[pyfunction]
+#[pyo3(signature = (images, bank=None))]
+fn pack_atlas(images: Bound<'_, PyAny>, bank: Option<u32>) -> PyResult<()> {
+    let atlas = if let Ok(dirname) = images.extract::<String>() {
+        pyxel::Atlas::pack_dir(&dirname, bank)
+    } else {
+        let images: Vec<(String, Image)> = images.extract()?;
+        let images: Vec<_> = images
+            .into_iter()
+            .map(|(name, image)| (name, image.inner))
+            .collect();
+        pyxel::Atlas::pack(&images, bank)
+    };
+    pyxel().atlas = atlas.map_err(PyValueError::new_err)?;
+    Ok(())
+}
+
+#[pyfunction]
+fn load_atlas(filename: &str) -> PyResult<()> {
+    pyxel().atlas = pyxel::Atlas::load(filename).map_err(PyValueError::new_err)?;
+    Ok(())
+}
+
+#[pyfunction]
+fn save_atlas(filename: &str) -> PyResult<()> {
+    pyxel().atlas.save(filename).map_err(PyValueError::new_err)
+}
+
+#[pyfunction]
+fn atlas_entry(name: &str) -> Option<(u32, u32, u32, u32, u32)> {
+    pyxel()
+        .atlas
+        .get(name)
+        .map(|entry| (entry.bank, entry.u, entry.v, entry.w, entry.h))
+}
+
+#[pyfunction]
+#[pyo3(signature = (x, y, tm, u, v, w, h, colkey=None, rotate=None, scale=None, time=None))]
 fn bltm(
     x: f64,
     y: f64,
@@ -154,15 +381,36 @@ fn bltm(
     colkey: Option<pyxel::Color>,
     rotate: Option<f64>,
     scale: Option<f64>,
+    time: Option<u32>,
 ) -> PyResult<()> {
+    let time = Some(time.unwrap_or_else(|| pyxel().frame_count));
     cast_pyany! {
         tm,
-        (u32, { pyxel().bltm(x, y, tm, u, v, w, h, colkey, rotate, scale); }),
-        (Tilemap, { pyxel().screen.lock().bltm(x, y, tm.inner, u, v, w, h, colkey, rotate, scale); })
+        (u32, {
+            let tilemap = pyxel().tilemaps.lock()[tm as usize].clone();
+            pyxel().screen.lock().bltm(x, y, tilemap, u, v, w, h, colkey, rotate, scale, time);
+        }),
+        (Tilemap, { pyxel().screen.lock().bltm(x, y, tm.inner, u, v, w, h, colkey, rotate, scale, time); }),
+        (ChunkedTilemap, { pyxel().screen.lock().bltm_chunked(x, y, tm.inner, u, v, w, h, colkey, rotate, scale, time); })
     }
     Ok(())
 }
 
+#[pyfunction]
+#[pyo3(signature = (x, y, sprite, flip_x=None, flip_y=None, colkey=None, rotate=None, scale=None))]
+fn draw_sprite(
+    x: f64,
+    y: f64,
+    sprite: PyRef<'_, Sprite>,
+    flip_x: Option<bool>,
+    flip_y: Option<bool>,
+    colkey: Option<pyxel::Color>,
+    rotate: Option<f64>,
+    scale: Option<f64>,
+) {
+    pyxel().draw_sprite(x, y, &sprite.inner, flip_x, flip_y, colkey, rotate, scale);
+}
+
 #[pyfunction]
 #[pyo3(signature = (x, y, s, col, font=None))]
 fn text(x: f64, y: f64, s: &str, col: pyxel::Color, font: Option<Font>) {
@@ -174,6 +422,77 @@ fn text(x: f64, y: f64, s: &str, col: pyxel::Color, font: Option<Font>) {
     pyxel().text(x, y, s, col, font);
 }
 
+#[pyfunction]
+#[pyo3(signature = (x, y, s, col, font=None, time=None))]
+fn text_rich(x: f64, y: f64, s: &str, col: pyxel::Color, font: Option<Font>, time: Option<u32>) {
+    let time = Some(time.unwrap_or_else(|| pyxel().frame_count));
+    pyxel()
+        .screen
+        .lock()
+        .text_rich(x, y, s, col, font.map(|font| font.inner), time);
+}
+
+#[pyfunction]
+#[pyo3(signature = (s, font=None))]
+fn text_width(s: &str, font: Option<Font>) -> i32 {
+    pyxel::Pyxel::text_width(s, font.map(|font| font.inner))
+}
+
+#[pyfunction]
+#[pyo3(signature = (s, font=None, line_spacing=None))]
+fn text_bounds(s: &str, font: Option<Font>, line_spacing: Option<i32>) -> (i32, i32) {
+    pyxel::Pyxel::text_bounds(s, font.map(|font| font.inner), line_spacing)
+}
+
+#[pyfunction]
+#[pyo3(signature = (x, y, w, h, s, col, font=None, align=None, line_spacing=None, wrap=None, ellipsis=None))]
+fn text_box(
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    s: &str,
+    col: pyxel::Color,
+    font: Option<Font>,
+    align: Option<u32>,
+    line_spacing: Option<i32>,
+    wrap: Option<bool>,
+    ellipsis: Option<bool>,
+) {
+    pyxel().text_box(
+        x,
+        y,
+        w,
+        h,
+        s,
+        col,
+        font.map(|font| font.inner),
+        align.map(pyxel::TextAlign::from_index),
+        line_spacing,
+        wrap,
+        ellipsis,
+    );
+}
+
+#[pyfunction]
+fn add_glyph(c: char, data: Vec<String>) -> PyResult<()> {
+    let data_refs: Vec<_> = data.iter().map(String::as_str).collect();
+    pyxel()
+        .add_glyph(c, &data_refs)
+        .map_err(PyValueError::new_err)
+}
+
+#[pyfunction]
+#[pyo3(signature = (colkey=None))]
+fn add_layer(colkey: Option<pyxel::Color>) -> Layer {
+    Layer::wrap(pyxel().add_layer(colkey))
+}
+
+#[pyfunction]
+fn clear_layers() {
+    pyxel().clear_layers();
+}
+
 #[pyfunction]
 fn image(img: u32) -> Image {
     IMAGE_ONCE.call_once(|| {
@@ -192,11 +511,32 @@ fn tilemap(tm: u32) -> Tilemap {
     Tilemap::wrap(pyxel().tilemaps.lock()[tm as usize].clone())
 }
 
+#[pyfunction]
+fn add_emitter(x: f64, y: f64) -> Emitter {
+    Emitter::wrap(pyxel().add_emitter(x, y))
+}
+
+#[pyfunction]
+fn clear_emitters() {
+    pyxel().clear_emitters();
+}
+
+#[pyfunction]
+fn draw_emitters() {
+    pyxel().draw_emitters();
+}
+
 pub fn add_graphics_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(clip, m)?)?;
     m.add_function(wrap_pyfunction!(camera, m)?)?;
     m.add_function(wrap_pyfunction!(pal, m)?)?;
+    m.add_function(wrap_pyfunction!(pal_cycle, m)?)?;
     m.add_function(wrap_pyfunction!(dither, m)?)?;
+    m.add_function(wrap_pyfunction!(stencil, m)?)?;
+    m.add_function(wrap_pyfunction!(stencil_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(stencil_set, m)?)?;
+    m.add_function(wrap_pyfunction!(stencil_clear, m)?)?;
+    m.add_function(wrap_pyfunction!(stencil_invert, m)?)?;
     m.add_function(wrap_pyfunction!(cls, m)?)?;
     m.add_function(wrap_pyfunction!(pget, m)?)?;
     m.add_function(wrap_pyfunction!(pset, m)?)?;
@@ -210,9 +550,31 @@ pub fn add_graphics_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(tri, m)?)?;
     m.add_function(wrap_pyfunction!(trib, m)?)?;
     m.add_function(wrap_pyfunction!(fill, m)?)?;
+    m.add_function(wrap_pyfunction!(linear_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(radial_gradient, m)?)?;
     m.add_function(wrap_pyfunction!(blt, m)?)?;
+    m.add_function(wrap_pyfunction!(blt_stretch, m)?)?;
+    m.add_function(wrap_pyfunction!(blt_scale, m)?)?;
+    m.add_function(wrap_pyfunction!(blt_tiled, m)?)?;
+    m.add_function(wrap_pyfunction!(blt9, m)?)?;
+    m.add_function(wrap_pyfunction!(blt_sprite, m)?)?;
+    m.add_function(wrap_pyfunction!(pack_atlas, m)?)?;
+    m.add_function(wrap_pyfunction!(load_atlas, m)?)?;
+    m.add_function(wrap_pyfunction!(save_atlas, m)?)?;
+    m.add_function(wrap_pyfunction!(atlas_entry, m)?)?;
     m.add_function(wrap_pyfunction!(bltm, m)?)?;
+    m.add_function(wrap_pyfunction!(draw_sprite, m)?)?;
     m.add_function(wrap_pyfunction!(text, m)?)?;
+    m.add_function(wrap_pyfunction!(text_rich, m)?)?;
+    m.add_function(wrap_pyfunction!(text_width, m)?)?;
+    m.add_function(wrap_pyfunction!(text_bounds, m)?)?;
+    m.add_function(wrap_pyfunction!(text_box, m)?)?;
+    m.add_function(wrap_pyfunction!(add_glyph, m)?)?;
+    m.add_function(wrap_pyfunction!(add_layer, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_layers, m)?)?;
+    m.add_function(wrap_pyfunction!(add_emitter, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_emitters, m)?)?;
+    m.add_function(wrap_pyfunction!(draw_emitters, m)?)?;
 
     // Deprecated functions
     m.add_function(wrap_pyfunction!(image, m)?)?;
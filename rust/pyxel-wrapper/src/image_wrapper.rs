@@ -1,8 +1,10 @@
 use std::ffi::CString;
 
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 
+use crate::chunked_tilemap_wrapper::ChunkedTilemap;
 use crate::font_wrapper::Font;
 use crate::pyxel_singleton::pyxel;
 use crate::tilemap_wrapper::Tilemap;
@@ -19,6 +21,42 @@ impl Image {
     }
 }
 
+#[pyclass]
+pub struct AsepriteData {
+    pub(crate) inner: pyxel::AsepriteData,
+}
+
+#[pymethods]
+impl AsepriteData {
+    #[getter]
+    pub fn width(&self) -> u32 {
+        self.inner.width
+    }
+
+    #[getter]
+    pub fn height(&self) -> u32 {
+        self.inner.height
+    }
+
+    #[getter]
+    pub fn frames(&self) -> Vec<(i32, i32, u32)> {
+        self.inner
+            .frames
+            .iter()
+            .map(|frame| (frame.u, frame.v, frame.duration))
+            .collect()
+    }
+
+    #[getter]
+    pub fn tags(&self) -> Vec<(String, u32, u32)> {
+        self.inner
+            .tags
+            .iter()
+            .map(|tag| (tag.name.clone(), tag.from, tag.to))
+            .collect()
+    }
+}
+
 #[pymethods]
 impl Image {
     #[new]
@@ -27,9 +65,23 @@ impl Image {
     }
 
     #[staticmethod]
-    #[pyo3(signature = (filename, incl_colors=None))]
-    pub fn from_image(filename: &str, incl_colors: Option<bool>) -> Self {
-        Self::wrap(pyxel::Image::from_image(filename, incl_colors))
+    #[pyo3(signature = (filename, incl_colors=None, dither=None, quantize=None, num_colors=None, transparent=None))]
+    pub fn from_image(
+        filename: &str,
+        incl_colors: Option<bool>,
+        dither: Option<u32>,
+        quantize: Option<u32>,
+        num_colors: Option<u32>,
+        transparent: Option<pyxel::Color>,
+    ) -> Self {
+        Self::wrap(pyxel::Image::from_image(
+            filename,
+            incl_colors,
+            dither.map(pyxel::Dither::from_index),
+            quantize.map(pyxel::Quantize::from_index),
+            num_colors,
+            transparent,
+        ))
     }
 
     #[getter]
@@ -60,13 +112,103 @@ impl Image {
         self.inner.lock().set(x, y, &data_refs);
     }
 
-    #[pyo3(signature = (x, y, filename, incl_colors=None))]
-    pub fn load(&self, x: i32, y: i32, filename: &str, incl_colors: Option<bool>) {
-        self.inner.lock().load(x, y, filename, incl_colors);
+    #[pyo3(signature = (x, y, filename, incl_colors=None, dither=None, quantize=None, num_colors=None, transparent=None))]
+    pub fn load(
+        &self,
+        x: i32,
+        y: i32,
+        filename: &str,
+        incl_colors: Option<bool>,
+        dither: Option<u32>,
+        quantize: Option<u32>,
+        num_colors: Option<u32>,
+        transparent: Option<pyxel::Color>,
+    ) {
+        self.inner.lock().load(
+            x,
+            y,
+            filename,
+            incl_colors,
+            dither.map(pyxel::Dither::from_index),
+            quantize.map(pyxel::Quantize::from_index),
+            num_colors,
+            transparent,
+        );
+    }
+
+    #[pyo3(signature = (x, y, filename, layer=None, columns=None, incl_colors=None))]
+    pub fn load_aseprite(
+        &self,
+        x: i32,
+        y: i32,
+        filename: &str,
+        layer: Option<&str>,
+        columns: Option<u32>,
+        incl_colors: Option<bool>,
+    ) -> PyResult<AsepriteData> {
+        self.inner
+            .lock()
+            .load_aseprite(x, y, filename, layer, columns, incl_colors)
+            .map(|inner| AsepriteData { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    #[pyo3(signature = (filename, scale, indexed=None, transparent=None, upscale=None))]
+    pub fn save(
+        &self,
+        filename: &str,
+        scale: u32,
+        indexed: Option<bool>,
+        transparent: Option<pyxel::Color>,
+        upscale: Option<u32>,
+    ) {
+        self.inner.lock().save(
+            filename,
+            scale,
+            indexed,
+            transparent,
+            upscale.map(pyxel::Upscale::from_index),
+        );
+    }
+
+    pub fn flipped_h(&self) -> Image {
+        Image::wrap(self.inner.lock().flipped_h())
+    }
+
+    pub fn flipped_v(&self) -> Image {
+        Image::wrap(self.inner.lock().flipped_v())
+    }
+
+    pub fn rotate90(&self) -> Image {
+        Image::wrap(self.inner.lock().rotate90())
+    }
+
+    pub fn crop(&self, x: i32, y: i32, w: u32, h: u32) -> Image {
+        Image::wrap(self.inner.lock().crop(x, y, w, h))
     }
 
-    pub fn save(&self, filename: &str, scale: u32) {
-        self.inner.lock().save(filename, scale);
+    pub fn resize(&self, w: u32, h: u32) -> Image {
+        Image::wrap(self.inner.lock().resize(w, h))
+    }
+
+    pub fn scale2x(&self) -> Image {
+        Image::wrap(self.inner.lock().scale2x())
+    }
+
+    pub fn scale3x(&self) -> Image {
+        Image::wrap(self.inner.lock().scale3x())
+    }
+
+    pub fn epx(&self) -> Image {
+        Image::wrap(self.inner.lock().epx())
+    }
+
+    pub fn upscaled(&self, upscale: u32) -> Image {
+        Image::wrap(
+            self.inner
+                .lock()
+                .upscaled(pyxel::Upscale::from_index(upscale)),
+        )
     }
 
     #[pyo3(signature = (x=None, y=None, w=None, h=None))]
@@ -115,6 +257,41 @@ impl Image {
         self.inner.lock().dither(alpha);
     }
 
+    #[pyo3(signature = (img=None, colkey=None))]
+    fn stencil(&self, img: Option<Bound<'_, PyAny>>, colkey: Option<pyxel::Color>) -> PyResult<()> {
+        let Some(img) = img else {
+            self.inner.lock().stencil0();
+            return Ok(());
+        };
+        cast_pyany! {
+            img,
+            (u32, {
+                let image = pyxel().images.lock()[img as usize].clone();
+                self.inner.lock().stencil(image, colkey);
+            }),
+            (Image, { self.inner.lock().stencil(img.inner, colkey); })
+        }
+        Ok(())
+    }
+
+    fn stencil_mode(&self, mode: u32) {
+        self.inner
+            .lock()
+            .stencil_mode(pyxel::StencilMode::from_index(mode));
+    }
+
+    fn stencil_set(&self) {
+        self.inner.lock().stencil_set();
+    }
+
+    fn stencil_clear(&self) {
+        self.inner.lock().stencil_clear();
+    }
+
+    fn stencil_invert(&self) {
+        self.inner.lock().stencil_invert();
+    }
+
     pub fn cls(&self, col: pyxel::Color) {
         self.inner.lock().cls(col);
     }
@@ -167,6 +344,22 @@ impl Image {
         self.inner.lock().fill(x, y, col);
     }
 
+    pub fn linear_gradient(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        angle: f64,
+        cols: Vec<pyxel::Color>,
+    ) {
+        self.inner.lock().linear_gradient(x, y, w, h, angle, &cols);
+    }
+
+    pub fn radial_gradient(&self, x: f64, y: f64, r: f64, cols: Vec<pyxel::Color>) {
+        self.inner.lock().radial_gradient(x, y, r, &cols);
+    }
+
     #[pyo3(signature = (x, y, img, u, v, w, h, colkey=None, rotate=None, scale=None))]
     pub fn blt(
         &self,
@@ -192,7 +385,124 @@ impl Image {
         Ok(())
     }
 
-    #[pyo3(signature = (x, y, tm, u, v, w, h, colkey=None, rotate=None, scale=None))]
+    #[pyo3(signature = (x, y, w, h, img, u, v, iw, ih, colkey=None, shear=None))]
+    pub fn blt_stretch(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        img: Bound<'_, PyAny>,
+        u: f64,
+        v: f64,
+        iw: f64,
+        ih: f64,
+        colkey: Option<pyxel::Color>,
+        shear: Option<f64>,
+    ) -> PyResult<()> {
+        cast_pyany! {
+            img,
+            (u32, {
+                let image = pyxel().images.lock()[img as usize].clone();
+                self.inner.lock().blt_stretch(x, y, w, h, image, u, v, iw, ih, colkey, shear);
+            }),
+            (Image, {
+                self.inner.lock().blt_stretch(x, y, w, h, img.inner, u, v, iw, ih, colkey, shear);
+            })
+        }
+        Ok(())
+    }
+
+    #[pyo3(signature = (x, y, img, u, v, w, h, colkey, scale_x, scale_y, shear=None))]
+    pub fn blt_scale(
+        &self,
+        x: f64,
+        y: f64,
+        img: Bound<'_, PyAny>,
+        u: f64,
+        v: f64,
+        w: f64,
+        h: f64,
+        colkey: Option<pyxel::Color>,
+        scale_x: f64,
+        scale_y: f64,
+        shear: Option<f64>,
+    ) -> PyResult<()> {
+        cast_pyany! {
+            img,
+            (u32, {
+                let image = pyxel().images.lock()[img as usize].clone();
+                self.inner.lock().blt_scale(x, y, image, u, v, w, h, colkey, scale_x, scale_y, shear);
+            }),
+            (Image, {
+                self.inner.lock().blt_scale(x, y, img.inner, u, v, w, h, colkey, scale_x, scale_y, shear);
+            })
+        }
+        Ok(())
+    }
+
+    #[pyo3(signature = (x, y, w, h, img, u, v, tw, th, colkey=None))]
+    pub fn blt_tiled(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        img: Bound<'_, PyAny>,
+        u: f64,
+        v: f64,
+        tw: f64,
+        th: f64,
+        colkey: Option<pyxel::Color>,
+    ) -> PyResult<()> {
+        cast_pyany! {
+            img,
+            (u32, {
+                let image = pyxel().images.lock()[img as usize].clone();
+                self.inner.lock().blt_tiled(x, y, w, h, image, u, v, tw, th, colkey);
+            }),
+            (Image, { self.inner.lock().blt_tiled(x, y, w, h, img.inner, u, v, tw, th, colkey); })
+        }
+        Ok(())
+    }
+
+    #[pyo3(signature = (x, y, w, h, img, u, v, iw, ih, left, top, right, bottom, colkey=None, tiled=None))]
+    pub fn blt9(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        img: Bound<'_, PyAny>,
+        u: f64,
+        v: f64,
+        iw: f64,
+        ih: f64,
+        left: f64,
+        top: f64,
+        right: f64,
+        bottom: f64,
+        colkey: Option<pyxel::Color>,
+        tiled: Option<bool>,
+    ) -> PyResult<()> {
+        cast_pyany! {
+            img,
+            (u32, {
+                let image = pyxel().images.lock()[img as usize].clone();
+                self.inner.lock().blt9(
+                    x, y, w, h, image, u, v, iw, ih, left, top, right, bottom, colkey, tiled,
+                );
+            }),
+            (Image, {
+                self.inner.lock().blt9(
+                    x, y, w, h, img.inner, u, v, iw, ih, left, top, right, bottom, colkey, tiled,
+                );
+            })
+        }
+        Ok(())
+    }
+
+    #[pyo3(signature = (x, y, tm, u, v, w, h, colkey=None, rotate=None, scale=None, time=None))]
     pub fn bltm(
         &self,
         x: f64,
@@ -205,14 +515,17 @@ impl Image {
         colkey: Option<pyxel::Color>,
         rotate: Option<f64>,
         scale: Option<f64>,
+        time: Option<u32>,
     ) -> PyResult<()> {
+        let time = Some(time.unwrap_or_else(|| pyxel().frame_count));
         cast_pyany! {
             tm,
             (u32, {
                 let tilemap = pyxel().tilemaps.lock()[tm as usize].clone();
-                self.inner.lock().bltm(x, y, tilemap, u, v, w, h, colkey, rotate, scale);
+                self.inner.lock().bltm(x, y, tilemap, u, v, w, h, colkey, rotate, scale, time);
             }),
-            (Tilemap, { self.inner.lock().bltm(x, y, tm.inner, u, v, w, h, colkey, rotate, scale); })
+            (Tilemap, { self.inner.lock().bltm(x, y, tm.inner, u, v, w, h, colkey, rotate, scale, time); }),
+            (ChunkedTilemap, { self.inner.lock().bltm_chunked(x, y, tm.inner, u, v, w, h, colkey, rotate, scale, time); })
         }
         Ok(())
     }
@@ -226,9 +539,56 @@ impl Image {
         };
         self.inner.lock().text(x, y, s, col, font);
     }
+
+    #[pyo3(signature = (x, y, s, col, font=None, time=None))]
+    pub fn text_rich(
+        &self,
+        x: f64,
+        y: f64,
+        s: &str,
+        col: pyxel::Color,
+        font: Option<Font>,
+        time: Option<u32>,
+    ) {
+        let time = Some(time.unwrap_or_else(|| pyxel().frame_count));
+        self.inner
+            .lock()
+            .text_rich(x, y, s, col, font.map(|font| font.inner), time);
+    }
+
+    #[pyo3(signature = (x, y, w, h, s, col, font=None, align=None, line_spacing=None, wrap=None, ellipsis=None))]
+    pub fn text_box(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        s: &str,
+        col: pyxel::Color,
+        font: Option<Font>,
+        align: Option<u32>,
+        line_spacing: Option<i32>,
+        wrap: Option<bool>,
+        ellipsis: Option<bool>,
+    ) {
+        self.inner.lock().text_box(
+            x,
+            y,
+            w,
+            h,
+            s,
+            col,
+            font.map(|font| font.inner),
+            align.map(pyxel::TextAlign::from_index),
+            line_spacing,
+            wrap,
+            ellipsis,
+        );
+    }
 }
 
 pub fn add_image_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Image>()?;
+    m.add_class::<AsepriteData>()?;
     Ok(())
 }
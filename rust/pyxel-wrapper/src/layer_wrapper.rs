@@ -0,0 +1,60 @@
+use pyo3::prelude::*;
+
+use crate::image_wrapper::Image;
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Layer {
+    pub(crate) inner: pyxel::SharedLayer,
+}
+
+impl Layer {
+    pub fn wrap(inner: pyxel::SharedLayer) -> Self {
+        Self { inner }
+    }
+}
+
+#[pymethods]
+impl Layer {
+    #[getter]
+    pub fn image(&self) -> Image {
+        Image::wrap(self.inner.lock().image.clone())
+    }
+
+    #[getter]
+    pub fn visible(&self) -> bool {
+        self.inner.lock().visible
+    }
+
+    #[setter]
+    pub fn set_visible(&self, visible: bool) {
+        self.inner.lock().visible = visible;
+    }
+
+    #[getter]
+    pub fn colkey(&self) -> Option<pyxel::Color> {
+        self.inner.lock().transparent
+    }
+
+    #[setter]
+    pub fn set_colkey(&self, colkey: Option<pyxel::Color>) {
+        self.inner.lock().transparent = colkey;
+    }
+
+    #[pyo3(signature = (x=None, y=None))]
+    pub fn camera(&self, x: Option<f64>, y: Option<f64>) -> PyResult<()> {
+        if let (Some(x), Some(y)) = (x, y) {
+            self.inner.lock().camera(x, y);
+        } else if (x, y) == (None, None) {
+            self.inner.lock().camera0();
+        } else {
+            python_type_error!("camera() takes 0 or 2 arguments");
+        }
+        Ok(())
+    }
+}
+
+pub fn add_layer_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Layer>()?;
+    Ok(())
+}
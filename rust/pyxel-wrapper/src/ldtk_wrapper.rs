@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::IntoPyObjectExt;
+
+use crate::tilemap_wrapper::Tilemap;
+
+fn value_to_py(py: Python, value: &pyxel::LdtkValue) -> PyResult<PyObject> {
+    match value {
+        pyxel::LdtkValue::Null => Ok(py.None()),
+        pyxel::LdtkValue::Bool(value) => value.into_py_any(py),
+        pyxel::LdtkValue::Int(value) => value.into_py_any(py),
+        pyxel::LdtkValue::Float(value) => value.into_py_any(py),
+        pyxel::LdtkValue::String(value) => value.into_py_any(py),
+        pyxel::LdtkValue::Point(x, y) => (*x, *y).into_py_any(py),
+        pyxel::LdtkValue::Array(values) => values
+            .iter()
+            .map(|value| value_to_py(py, value))
+            .collect::<PyResult<Vec<_>>>()?
+            .into_py_any(py),
+    }
+}
+
+fn fields_to_py(
+    py: Python,
+    fields: &BTreeMap<String, pyxel::LdtkValue>,
+) -> PyResult<BTreeMap<String, PyObject>> {
+    fields
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), value_to_py(py, value)?)))
+        .collect()
+}
+
+#[pyclass]
+pub struct LdtkProject {
+    levels: Vec<Arc<pyxel::LdtkLevel>>,
+    tileset_banks: BTreeMap<i64, u32>,
+}
+
+#[pymethods]
+impl LdtkProject {
+    #[staticmethod]
+    #[pyo3(signature = (filename, first_bank=None))]
+    pub fn load(filename: &str, first_bank: Option<u32>) -> PyResult<Self> {
+        let project =
+            pyxel::LdtkProject::load(filename, first_bank).map_err(PyValueError::new_err)?;
+        Ok(Self {
+            levels: project.levels.into_iter().map(Arc::new).collect(),
+            tileset_banks: project.tileset_banks,
+        })
+    }
+
+    #[getter]
+    pub fn levels(&self) -> Vec<LdtkLevel> {
+        self.levels
+            .iter()
+            .map(|level| LdtkLevel {
+                inner: level.clone(),
+            })
+            .collect()
+    }
+
+    #[getter]
+    pub fn tileset_banks(&self) -> BTreeMap<i64, u32> {
+        self.tileset_banks.clone()
+    }
+
+    pub fn level(&self, identifier: &str) -> Option<LdtkLevel> {
+        self.levels
+            .iter()
+            .find(|level| level.identifier == identifier)
+            .map(|level| LdtkLevel {
+                inner: level.clone(),
+            })
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct LdtkLevel {
+    inner: Arc<pyxel::LdtkLevel>,
+}
+
+#[pymethods]
+impl LdtkLevel {
+    #[getter]
+    pub fn identifier(&self) -> String {
+        self.inner.identifier.clone()
+    }
+
+    #[getter]
+    pub fn world_x(&self) -> i32 {
+        self.inner.world_x
+    }
+
+    #[getter]
+    pub fn world_y(&self) -> i32 {
+        self.inner.world_y
+    }
+
+    #[getter]
+    pub fn width(&self) -> u32 {
+        self.inner.width
+    }
+
+    #[getter]
+    pub fn height(&self) -> u32 {
+        self.inner.height
+    }
+
+    #[getter]
+    pub fn layers(&self) -> Vec<LdtkLayer> {
+        (0..self.inner.layers.len())
+            .map(|index| LdtkLayer {
+                level: self.inner.clone(),
+                index,
+            })
+            .collect()
+    }
+
+    #[getter]
+    pub fn entities(&self) -> Vec<LdtkEntity> {
+        (0..self.inner.entities.len())
+            .map(|index| LdtkEntity {
+                level: self.inner.clone(),
+                index,
+            })
+            .collect()
+    }
+
+    #[getter]
+    pub fn fields(&self, py: Python) -> PyResult<BTreeMap<String, PyObject>> {
+        fields_to_py(py, &self.inner.fields)
+    }
+
+    pub fn layer(&self, identifier: &str) -> Option<LdtkLayer> {
+        self.inner
+            .layers
+            .iter()
+            .position(|layer| layer.identifier == identifier)
+            .map(|index| LdtkLayer {
+                level: self.inner.clone(),
+                index,
+            })
+    }
+
+    pub fn find_entities(&self, identifier: &str) -> Vec<LdtkEntity> {
+        self.entities()
+            .into_iter()
+            .filter(|entity| entity.entity().identifier == identifier)
+            .collect()
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct LdtkLayer {
+    level: Arc<pyxel::LdtkLevel>,
+    index: usize,
+}
+
+impl LdtkLayer {
+    fn layer(&self) -> &pyxel::LdtkLayer {
+        &self.level.layers[self.index]
+    }
+}
+
+#[pymethods]
+impl LdtkLayer {
+    #[getter]
+    pub fn identifier(&self) -> String {
+        self.layer().identifier.clone()
+    }
+
+    #[getter]
+    pub fn tilemap(&self) -> Tilemap {
+        Tilemap::wrap(self.layer().tilemap.clone())
+    }
+
+    #[getter]
+    pub fn int_grid(&self) -> Vec<u32> {
+        self.layer().int_grid.clone()
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct LdtkEntity {
+    level: Arc<pyxel::LdtkLevel>,
+    index: usize,
+}
+
+impl LdtkEntity {
+    fn entity(&self) -> &pyxel::LdtkEntity {
+        &self.level.entities[self.index]
+    }
+}
+
+#[pymethods]
+impl LdtkEntity {
+    #[getter]
+    pub fn identifier(&self) -> String {
+        self.entity().identifier.clone()
+    }
+
+    #[getter]
+    pub fn x(&self) -> i32 {
+        self.entity().x
+    }
+
+    #[getter]
+    pub fn y(&self) -> i32 {
+        self.entity().y
+    }
+
+    #[getter]
+    pub fn grid_x(&self) -> i32 {
+        self.entity().grid_x
+    }
+
+    #[getter]
+    pub fn grid_y(&self) -> i32 {
+        self.entity().grid_y
+    }
+
+    #[getter]
+    pub fn width(&self) -> u32 {
+        self.entity().width
+    }
+
+    #[getter]
+    pub fn height(&self) -> u32 {
+        self.entity().height
+    }
+
+    #[getter]
+    pub fn fields(&self, py: Python) -> PyResult<BTreeMap<String, PyObject>> {
+        fields_to_py(py, &self.entity().fields)
+    }
+
+    pub fn field(&self, py: Python, name: &str) -> PyResult<Option<PyObject>> {
+        self.entity()
+            .field(name)
+            .map(|value| value_to_py(py, value))
+            .transpose()
+    }
+}
+
+pub fn add_ldtk_classes(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<LdtkProject>()?;
+    m.add_class::<LdtkLevel>()?;
+    m.add_class::<LdtkLayer>()?;
+    m.add_class::<LdtkEntity>()?;
+    Ok(())
+}
@@ -16,17 +16,23 @@
 #[macro_use]
 mod utils;
 mod audio_wrapper;
+mod camera_wrapper;
 mod channel_wrapper;
+mod chunked_tilemap_wrapper;
 mod constant_wrapper;
+mod emitter_wrapper;
 mod font_wrapper;
 mod graphics_wrapper;
 mod image_wrapper;
 mod input_wrapper;
+mod layer_wrapper;
+mod ldtk_wrapper;
 mod math_wrapper;
 mod music_wrapper;
 mod pyxel_singleton;
 mod resource_wrapper;
 mod sound_wrapper;
+mod sprite_wrapper;
 mod system_wrapper;
 mod tilemap_wrapper;
 mod tone_wrapper;
@@ -39,10 +45,16 @@ fn pyxel_wrapper(_py: Python, m: Bound<'_, PyModule>) -> PyResult<()> {
     crate::font_wrapper::add_font_class(&m)?;
     crate::image_wrapper::add_image_class(&m)?;
     crate::tilemap_wrapper::add_tilemap_class(&m)?;
+    crate::chunked_tilemap_wrapper::add_chunked_tilemap_class(&m)?;
+    crate::layer_wrapper::add_layer_class(&m)?;
+    crate::ldtk_wrapper::add_ldtk_classes(&m)?;
     crate::channel_wrapper::add_channel_class(&m)?;
     crate::tone_wrapper::add_tone_class(&m)?;
     crate::sound_wrapper::add_sound_class(&m)?;
     crate::music_wrapper::add_music_class(&m)?;
+    crate::sprite_wrapper::add_sprite_class(&m)?;
+    crate::emitter_wrapper::add_emitter_class(&m)?;
+    crate::camera_wrapper::add_camera_class(&m)?;
 
     crate::constant_wrapper::add_module_constants(&m)?;
     crate::variable_wrapper::add_module_variables(&m)?;
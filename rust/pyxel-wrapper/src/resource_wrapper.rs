@@ -51,9 +51,9 @@ fn save(
 }
 
 #[pyfunction]
-#[pyo3(signature = (scale=None))]
-fn screenshot(scale: Option<u32>) {
-    pyxel().screenshot(scale);
+#[pyo3(signature = (scale=None, upscale=None))]
+fn screenshot(scale: Option<u32>, upscale: Option<u32>) {
+    pyxel().screenshot(scale, upscale.map(pyxel::Upscale::from_index));
 }
 
 #[pyfunction]
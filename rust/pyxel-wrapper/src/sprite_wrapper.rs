@@ -0,0 +1,106 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::image_wrapper::AsepriteData;
+use crate::pyxel_singleton::pyxel;
+
+#[pyclass]
+pub struct Sprite {
+    pub(crate) inner: pyxel::Sprite,
+}
+
+#[pymethods]
+impl Sprite {
+    #[new]
+    pub fn new() -> Self {
+        Self {
+            inner: pyxel::Sprite::new(),
+        }
+    }
+
+    #[getter]
+    pub fn animations(&self) -> Vec<String> {
+        self.inner.animations.keys().cloned().collect()
+    }
+
+    #[getter]
+    pub fn current(&self) -> Option<String> {
+        self.inner.current().map(str::to_string)
+    }
+
+    #[pyo3(signature = (name, frames, loop_mode=None))]
+    pub fn add_animation(
+        &mut self,
+        name: &str,
+        frames: Vec<(u32, f64, f64, f64, f64, u32)>,
+        loop_mode: Option<u32>,
+    ) {
+        let frames: Vec<_> = frames
+            .into_iter()
+            .map(|(img, u, v, w, h, duration)| pyxel::AnimationFrame {
+                img,
+                u,
+                v,
+                w,
+                h,
+                duration,
+            })
+            .collect();
+        let loop_mode = pyxel::LoopMode::from_index(loop_mode.unwrap_or(pyxel::LOOP_REPEAT));
+        self.inner
+            .add_animation(name, pyxel::Animation::new(&frames, loop_mode));
+    }
+
+    #[pyo3(signature = (name, data, img, tag=None))]
+    pub fn add_aseprite_animation(
+        &mut self,
+        name: &str,
+        data: PyRef<'_, AsepriteData>,
+        img: u32,
+        tag: Option<&str>,
+    ) -> PyResult<()> {
+        let animation =
+            pyxel::Animation::from_aseprite(&data.inner, img, tag).ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "Tag '{}' not found in Aseprite data",
+                    tag.unwrap_or("")
+                ))
+            })?;
+        self.inner.add_animation(name, animation);
+        Ok(())
+    }
+
+    #[pyo3(signature = (name, restart=None))]
+    pub fn play(&mut self, name: &str, restart: Option<bool>) -> PyResult<()> {
+        self.inner
+            .play(name, pyxel().frame_count, restart)
+            .map_err(PyValueError::new_err)
+    }
+
+    pub fn pause(&mut self) {
+        self.inner.pause(pyxel().frame_count);
+    }
+
+    pub fn resume(&mut self) {
+        self.inner.resume(pyxel().frame_count);
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.inner.is_playing()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished(pyxel().frame_count)
+    }
+
+    pub fn frame(&self) -> Option<(u32, f64, f64, f64, f64)> {
+        self.inner
+            .frame(pyxel().frame_count)
+            .map(|frame| (frame.img, frame.u, frame.v, frame.w, frame.h))
+    }
+}
+
+pub fn add_sprite_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Sprite>()?;
+    Ok(())
+}
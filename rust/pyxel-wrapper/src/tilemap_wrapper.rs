@@ -1,6 +1,7 @@
 use std::ffi::CString;
 use std::sync::Once;
 
+use pyo3::exceptions::{PyIOError, PyValueError};
 use pyo3::prelude::*;
 
 use crate::image_wrapper::Image;
@@ -23,6 +24,59 @@ impl Tilemap {
     }
 }
 
+#[pyclass]
+pub struct Terrain {
+    pub(crate) inner: pyxel::Terrain,
+}
+
+#[pymethods]
+impl Terrain {
+    #[new]
+    pub fn new(layout: u32) -> Self {
+        Self {
+            inner: pyxel::Terrain::new(pyxel::AutotileLayout::from_index(layout)),
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (layout, tile_x, tile_y, columns=None))]
+    pub fn from_sheet(
+        layout: u32,
+        tile_x: pyxel::ImageTileCoord,
+        tile_y: pyxel::ImageTileCoord,
+        columns: Option<u32>,
+    ) -> PyResult<Self> {
+        pyxel::Terrain::from_sheet(
+            pyxel::AutotileLayout::from_index(layout),
+            tile_x,
+            tile_y,
+            columns,
+        )
+        .map(|inner| Self { inner })
+        .map_err(PyValueError::new_err)
+    }
+
+    #[getter]
+    pub fn layout(&self) -> u32 {
+        match self.inner.layout {
+            pyxel::AutotileLayout::Blob16 => pyxel::AUTOTILE_BLOB16,
+            pyxel::AutotileLayout::Blob47 => pyxel::AUTOTILE_BLOB47,
+        }
+    }
+
+    pub fn masks(&self) -> Vec<u8> {
+        self.inner.layout.masks()
+    }
+
+    pub fn set_tile(&mut self, mask: u8, tile: pyxel::Tile) {
+        self.inner.set_tile(mask, tile);
+    }
+
+    pub fn tile(&self, mask: u8) -> Option<pyxel::Tile> {
+        self.inner.tile(mask)
+    }
+}
+
 #[pymethods]
 impl Tilemap {
     #[new]
@@ -92,6 +146,21 @@ impl Tilemap {
         self.inner.lock().load(x, y, filename, layer);
     }
 
+    #[pyo3(signature = (filename, tileset_filename=None))]
+    pub fn save_tmx(&self, filename: &str, tileset_filename: Option<&str>) -> PyResult<()> {
+        self.inner
+            .lock()
+            .save_tmx(filename, tileset_filename)
+            .map_err(PyIOError::new_err)
+    }
+
+    pub fn save_csv(&self, filename: &str) -> PyResult<()> {
+        self.inner
+            .lock()
+            .save_csv(filename)
+            .map_err(PyIOError::new_err)
+    }
+
     #[pyo3(signature = (x=None, y=None, w=None, h=None))]
     pub fn clip(
         &self,
@@ -201,6 +270,119 @@ impl Tilemap {
         Ok(())
     }
 
+    pub fn set_animation(&self, tile: pyxel::Tile, frames: Vec<(pyxel::Tile, u32)>) {
+        self.inner.lock().set_animation(tile, &frames);
+    }
+
+    pub fn set_attr(&self, tile: pyxel::Tile, flags: u32) {
+        self.inner.lock().set_attr(tile, flags);
+    }
+
+    pub fn attr(&self, tile: pyxel::Tile) -> u32 {
+        self.inner.lock().attr(tile)
+    }
+
+    pub fn attr_at(&self, x: i32, y: i32) -> u32 {
+        self.inner.lock().attr_at(x, y)
+    }
+
+    pub fn rect_tiles(&self, x: f64, y: f64, w: f64, h: f64, mask: u32) -> Vec<(i32, i32)> {
+        self.inner.lock().rect_tiles(x, y, w, h, mask)
+    }
+
+    pub fn collide_rect(&self, x: f64, y: f64, w: f64, h: f64, mask: u32) -> bool {
+        self.inner.lock().collide_rect(x, y, w, h, mask)
+    }
+
+    pub fn sweep_rect(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        dx: f64,
+        dy: f64,
+        mask: u32,
+    ) -> (f64, f64, bool, bool) {
+        let result = self.inner.lock().sweep_rect(x, y, w, h, dx, dy, mask);
+        (result.x, result.y, result.hit_x, result.hit_y)
+    }
+
+    pub fn raycast(
+        &self,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        mask: u32,
+    ) -> Option<(f64, f64, i32, i32)> {
+        self.inner
+            .lock()
+            .raycast(x1, y1, x2, y2, mask)
+            .map(|hit| (hit.x, hit.y, hit.tile_x, hit.tile_y))
+    }
+
+    pub fn autotile(&self, x: i32, y: i32, w: u32, h: u32, terrain: PyRef<'_, Terrain>) {
+        self.inner.lock().autotile(x, y, w, h, &terrain.inner);
+    }
+
+    pub fn pset_terrain(&self, x: i32, y: i32, terrain: PyRef<'_, Terrain>) {
+        self.inner.lock().pset_terrain(x, y, &terrain.inner);
+    }
+
+    #[pyo3(signature = (start, goal, mask, diagonal=None, cut_corners=None))]
+    pub fn find_path(
+        &self,
+        start: (i32, i32),
+        goal: (i32, i32),
+        mask: u32,
+        diagonal: Option<bool>,
+        cut_corners: Option<bool>,
+    ) -> Option<Vec<(i32, i32)>> {
+        self.inner
+            .lock()
+            .find_path_by_attr(start, goal, mask, path_rules(diagonal, cut_corners))
+    }
+
+    #[pyo3(signature = (goals, mask, diagonal=None, cut_corners=None))]
+    pub fn distance_field(
+        &self,
+        goals: Vec<(i32, i32)>,
+        mask: u32,
+        diagonal: Option<bool>,
+        cut_corners: Option<bool>,
+    ) -> Vec<Vec<f64>> {
+        let field = self.inner.lock().distance_field_by_attr(
+            &goals,
+            mask,
+            path_rules(diagonal, cut_corners),
+        );
+        field
+            .data
+            .chunks(field.width.max(1) as usize)
+            .map(<[f64]>::to_vec)
+            .collect()
+    }
+
+    #[pyo3(signature = (goals, mask, diagonal=None, cut_corners=None))]
+    pub fn flow_field(
+        &self,
+        goals: Vec<(i32, i32)>,
+        mask: u32,
+        diagonal: Option<bool>,
+        cut_corners: Option<bool>,
+    ) -> Vec<Vec<(i32, i32)>> {
+        let field =
+            self.inner
+                .lock()
+                .flow_field_by_attr(&goals, mask, path_rules(diagonal, cut_corners));
+        field
+            .data
+            .chunks(field.width.max(1) as usize)
+            .map(<[(i32, i32)]>::to_vec)
+            .collect()
+    }
+
     #[getter]
     pub fn image(&self) -> Image {
         IMAGE_ONCE.call_once(|| {
@@ -245,7 +427,15 @@ impl Tilemap {
     }
 }
 
+fn path_rules(diagonal: Option<bool>, cut_corners: Option<bool>) -> pyxel::PathRules {
+    pyxel::PathRules {
+        diagonal: diagonal.unwrap_or(false),
+        cut_corners: cut_corners.unwrap_or(false),
+    }
+}
+
 pub fn add_tilemap_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Tilemap>()?;
+    m.add_class::<Terrain>()?;
     Ok(())
 }
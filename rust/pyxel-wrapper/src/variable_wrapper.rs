@@ -1,6 +1,7 @@
 use pyo3::exceptions::PyAttributeError;
 use pyo3::prelude::*;
 
+use crate::camera_wrapper::ViewCamera;
 use crate::channel_wrapper::Channel;
 use crate::image_wrapper::Image;
 use crate::music_wrapper::Music;
@@ -75,6 +76,7 @@ fn __getattr__(py: Python, name: &str) -> PyResult<PyObject> {
         "screen" => class_to_pyobj!(py, Image::wrap(pyxel().screen.clone())),
         "cursor" => class_to_pyobj!(py, Image::wrap(pyxel().cursor.clone())),
         "font" => class_to_pyobj!(py, Image::wrap(pyxel().font.clone())),
+        "view_camera" => class_to_pyobj!(py, ViewCamera),
 
         // Audio
         "channels" => class_to_pyobj!(py, Channels::wrap(0)),